@@ -0,0 +1,433 @@
+//! Running one prompt through the `claude` CLI (or a configured stand-in) and reading its
+//! `stream-json` transcript.
+
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+
+/// Replies longer than this are truncated.
+pub const MAX_OUTPUT_BYTES: usize = 500_000; // 500KB per member
+
+/// What a spawned Claude instance is allowed to do with tools.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    /// Only tools that read the repository (Read, Glob, Grep, LS)
+    ReadOnly,
+    /// No tools at all; the model answers from the prompt alone
+    None,
+    /// Every tool, including shell and file edits, without prompting
+    Full,
+}
+
+const READ_ONLY_TOOLS: [&str; 4] = ["Read", "Glob", "Grep", "LS"];
+const OTHER_TOOLS: [&str; 10] = [
+    "Bash", "Edit", "MultiEdit", "Write", "NotebookEdit",
+    "WebFetch", "WebSearch", "Task", "TodoWrite", "KillShell",
+];
+
+impl Permission {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Permission::ReadOnly => "read-only",
+            Permission::None => "none",
+            Permission::Full => "full",
+        }
+    }
+
+    /// Translate the policy into `claude` CLI flags.
+    pub fn claude_args(self) -> Vec<String> {
+        match self {
+            Permission::ReadOnly => vec![
+                "--allowedTools".to_string(),
+                READ_ONLY_TOOLS.join(","),
+                "--disallowedTools".to_string(),
+                OTHER_TOOLS.join(","),
+            ],
+            Permission::None => vec![
+                "--disallowedTools".to_string(),
+                READ_ONLY_TOOLS.iter().chain(OTHER_TOOLS.iter()).copied().collect::<Vec<_>>().join(","),
+            ],
+            Permission::Full => vec!["--dangerously-skip-permissions".to_string()],
+        }
+    }
+}
+
+/// The CLI each member and the synthesis is run with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Backend {
+    pub command: String,
+    /// Extra arguments passed before council's own
+    pub args: Vec<String>,
+}
+
+impl Default for Backend {
+    fn default() -> Backend {
+        Backend { command: "claude".to_string(), args: Vec::new() }
+    }
+}
+
+impl Backend {
+    pub fn describe(&self) -> String {
+        std::iter::once(self.command.as_str()).chain(self.args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Token usage and cost reported by the CLI for one invocation.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Usage {
+    /// Prompt tokens, including cache reads and writes
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl Usage {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    fn from_json(result: &serde_json::Value) -> Usage {
+        let tokens = |key: &str| result["usage"][key].as_u64().unwrap_or(0);
+        Usage {
+            input_tokens: tokens("input_tokens")
+                + tokens("cache_creation_input_tokens")
+                + tokens("cache_read_input_tokens"),
+            output_tokens: tokens("output_tokens"),
+            cost_usd: result["total_cost_usd"]
+                .as_f64()
+                .or_else(|| result["cost_usd"].as_f64())
+                .unwrap_or(0.0),
+        }
+    }
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+/// What one `claude` invocation produced.
+pub struct ClaudeReply {
+    pub text: String,
+    pub usage: Usage,
+    /// False when the CLI exited non-zero or flagged the result as an error
+    pub success: bool,
+}
+
+/// What a running member has produced so far, reported on every line of its output stream.
+#[derive(Clone, Debug, Default)]
+pub struct StreamProgress {
+    pub bytes: usize,
+    pub output_tokens: u64,
+    /// Tool the member is currently using, if any
+    pub tool: Option<String>,
+    /// Text that arrived with this line
+    pub text: Option<String>,
+}
+
+/// Accumulates a `stream-json` transcript line by line.
+#[derive(Default)]
+struct StreamState {
+    progress: StreamProgress,
+    /// Output tokens per assistant message id (each content block repeats its message's usage)
+    message_tokens: HashMap<String, u64>,
+    /// Assistant text so far, in case the stream ends without a result
+    text: String,
+    /// Lines that were not JSON
+    raw: String,
+    result: Option<serde_json::Value>,
+}
+
+impl StreamState {
+    fn feed(&mut self, line: &str) {
+        self.progress.bytes += line.len() + 1;
+        self.progress.text = None;
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(line) else {
+            self.raw.push_str(line);
+            self.raw.push('\n');
+            return;
+        };
+        match json["type"].as_str() {
+            Some("assistant") => {
+                let message = &json["message"];
+                if let (Some(id), Some(tokens)) = (message["id"].as_str(), message["usage"]["output_tokens"].as_u64()) {
+                    self.message_tokens.insert(id.to_string(), tokens);
+                    self.progress.output_tokens = self.message_tokens.values().sum();
+                }
+                for block in message["content"].as_array().into_iter().flatten() {
+                    match block["type"].as_str() {
+                        Some("text") => {
+                            let text = block["text"].as_str().unwrap_or_default();
+                            self.text.push_str(text);
+                            self.progress.text.get_or_insert_with(String::new).push_str(text);
+                            self.progress.tool = None;
+                        }
+                        Some("tool_use") => self.progress.tool = block["name"].as_str().map(str::to_string),
+                        _ => {}
+                    }
+                }
+            }
+            // Tool results coming back: the member is thinking again
+            Some("user") => self.progress.tool = None,
+            Some("result") => self.result = Some(json),
+            _ => {}
+        }
+    }
+}
+
+/// Why a `claude` invocation produced no reply.
+#[derive(Debug)]
+pub enum ClaudeError {
+    Failed(String),
+    TimedOut(u64),
+}
+
+impl std::fmt::Display for ClaudeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClaudeError::Failed(e) => write!(f, "Process failed: {}", e),
+            ClaudeError::TimedOut(secs) => write!(f, "Timed out after {}s", secs),
+        }
+    }
+}
+
+/// Cut `text` to at most MAX_OUTPUT_BYTES without splitting a character.
+fn truncate_output(mut text: String) -> String {
+    if text.len() <= MAX_OUTPUT_BYTES {
+        return text;
+    }
+    let mut end = MAX_OUTPUT_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    format!("{}\n\n[Output truncated at {}KB]", text, MAX_OUTPUT_BYTES / 1000)
+}
+
+/// Run one prompt through the backend, reporting stream progress to `progress` as it arrives.
+pub async fn run_claude(
+    prompt: &str,
+    timeout_secs: u64,
+    model: Option<&str>,
+    permission: Permission,
+    workdir: Option<&Path>,
+    backend: &Backend,
+    progress: Option<&(dyn Fn(&StreamProgress) + Send + Sync)>,
+) -> Result<ClaudeReply, ClaudeError> {
+    let result = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+        let mut cmd = Command::new(&backend.command);
+        cmd.args(&backend.args);
+        cmd.args(["-p", prompt, "--output-format", "stream-json", "--verbose"]);
+        cmd.args(permission.claude_args());
+
+        if let Some(dir) = workdir {
+            cmd.current_dir(dir);
+        }
+
+        if let Some(m) = model {
+            cmd.args(["--model", m]);
+        }
+
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+
+        // Both pipes are drained together so a chatty stderr cannot block the process
+        let read_stdout = async {
+            let mut stream = StreamState::default();
+            let mut line = Vec::new();
+            while stdout.read_until(b'\n', &mut line).await.map(|n| n > 0).unwrap_or(false) {
+                stream.feed(String::from_utf8_lossy(&line).trim_end());
+                if let Some(report) = progress {
+                    report(&stream.progress);
+                }
+                line.clear();
+            }
+            stream
+        };
+        let read_stderr = async {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf).await;
+            buf
+        };
+        let (stream, stderr) = tokio::join!(read_stdout, read_stderr);
+        let status = child.wait().await?;
+        Ok::<_, std::io::Error>((stream, stderr, status))
+    })
+    .await;
+
+    match result {
+        Ok(Ok((stream, stderr, status))) => {
+            // The final result event carries the answer plus usage; fall back to what streamed
+            let mut success = status.success();
+            let (mut text, usage) = match stream.result {
+                Some(json) if json["result"].is_string() => {
+                    success &= !json["is_error"].as_bool().unwrap_or(false);
+                    (json["result"].as_str().unwrap_or_default().to_string(), Usage::from_json(&json))
+                }
+                _ if !stream.text.is_empty() => (stream.text, Usage::default()),
+                _ => (stream.raw, Usage::default()),
+            };
+
+            if !stderr.is_empty() {
+                text.push_str("\n[stderr]: ");
+                text.push_str(&String::from_utf8_lossy(&stderr));
+            }
+
+            Ok(ClaudeReply { text: truncate_output(text), usage, success })
+        }
+        Ok(Err(e)) => Err(ClaudeError::Failed(e.to_string())),
+        Err(_) => Err(ClaudeError::TimedOut(timeout_secs)),
+    }
+}
+
+/// Whether the backend's command can be found on the PATH.
+pub fn backend_installed(backend: &Backend) -> bool {
+    std::process::Command::new("which")
+        .arg(&backend.command)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A backend whose whole stream-json transcript is the line `output`.
+    fn fake(output: &str) -> Backend {
+        Backend {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), format!("printf '%s\\n' '{}'", output), "fake-claude".to_string()],
+        }
+    }
+
+    async fn reply(output: &str) -> ClaudeReply {
+        run_claude("prompt", 10, None, Permission::ReadOnly, None, &fake(output), None).await.unwrap()
+    }
+
+    #[test]
+    fn usage_counts_cache_tokens_as_input() {
+        let result = serde_json::json!({
+            "total_cost_usd": 0.25,
+            "usage": {
+                "input_tokens": 10,
+                "cache_creation_input_tokens": 200,
+                "cache_read_input_tokens": 3000,
+                "output_tokens": 40
+            }
+        });
+        let usage = Usage::from_json(&result);
+        assert_eq!((usage.input_tokens, usage.output_tokens, usage.total_tokens()), (3210, 40, 3250));
+        assert_eq!(usage.cost_usd, 0.25);
+    }
+
+    #[test]
+    fn usage_falls_back_to_older_cost_field_and_zero() {
+        let older = Usage::from_json(&serde_json::json!({ "cost_usd": 0.5, "usage": { "output_tokens": 7 } }));
+        assert_eq!((older.input_tokens, older.output_tokens, older.cost_usd), (0, 7, 0.5));
+        let missing = Usage::from_json(&serde_json::json!({ "result": "text" }));
+        assert_eq!((missing.total_tokens(), missing.cost_usd), (0, 0.0));
+
+        let mut total = older;
+        total += Usage { input_tokens: 3, output_tokens: 1, cost_usd: 0.25 };
+        assert_eq!((total.input_tokens, total.output_tokens, total.cost_usd), (3, 8, 0.75));
+    }
+
+    #[tokio::test]
+    async fn result_event_carries_text_and_usage() {
+        let reply = reply(concat!(
+            r#"{"type":"result","subtype":"success","is_error":false,"result":"- P1 fix it","#,
+            r#""total_cost_usd":0.02,"usage":{"input_tokens":100,"output_tokens":50}}"#
+        ))
+        .await;
+        assert!(reply.success);
+        assert_eq!(reply.text, "- P1 fix it");
+        assert_eq!((reply.usage.input_tokens, reply.usage.output_tokens, reply.usage.cost_usd), (100, 50, 0.02));
+    }
+
+    #[tokio::test]
+    async fn error_result_is_not_a_success() {
+        let reply = reply(r#"{"type":"result","is_error":true,"result":"Credit balance too low","total_cost_usd":0}"#).await;
+        assert!(!reply.success);
+        assert_eq!(reply.text, "Credit balance too low");
+    }
+
+    #[tokio::test]
+    async fn output_without_a_result_is_kept_uncharged() {
+        let reply = reply("not json at all").await;
+        assert!(reply.success);
+        assert_eq!(reply.text, "not json at all\n");
+        assert_eq!(reply.usage.total_tokens(), 0);
+    }
+
+    fn assistant(id: &str, output_tokens: u64, content: serde_json::Value) -> String {
+        serde_json::json!({
+            "type": "assistant",
+            "message": { "id": id, "usage": { "output_tokens": output_tokens }, "content": content }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn stream_tracks_text_tools_and_tokens() {
+        let mut stream = StreamState::default();
+        let line = assistant("m1", 5, serde_json::json!([{ "type": "text", "text": "Looking" }]));
+        stream.feed(&line);
+        assert_eq!(stream.progress.text.as_deref(), Some("Looking"));
+        assert_eq!((stream.progress.bytes, stream.progress.output_tokens), (line.len() + 1, 5));
+
+        // Every content block of a message repeats its usage; it is only counted once
+        stream.feed(&assistant("m1", 8, serde_json::json!([{ "type": "tool_use", "name": "Grep" }])));
+        assert_eq!(stream.progress.tool.as_deref(), Some("Grep"));
+        assert_eq!(stream.progress.text, None);
+        assert_eq!(stream.progress.output_tokens, 8);
+
+        stream.feed(r#"{"type":"user","message":{"content":[{"type":"tool_result"}]}}"#);
+        assert_eq!(stream.progress.tool, None);
+
+        stream.feed(&assistant("m2", 4, serde_json::json!([{ "type": "text", "text": " done" }])));
+        assert_eq!(stream.progress.output_tokens, 12);
+        assert_eq!(stream.text, "Looking done");
+        assert!(stream.result.is_none() && stream.raw.is_empty());
+
+        stream.feed(r#"{"type":"result","result":"Looking done"}"#);
+        assert_eq!(stream.result.as_ref().unwrap()["result"], "Looking done");
+    }
+
+    #[test]
+    fn stream_keeps_lines_that_are_not_json() {
+        let mut stream = StreamState::default();
+        stream.feed("Error: not logged in");
+        stream.feed(r#"{"type":"system","subtype":"init"}"#);
+        assert_eq!(stream.raw, "Error: not logged in\n");
+        assert!(stream.text.is_empty() && stream.result.is_none());
+    }
+
+    #[tokio::test]
+    async fn progress_is_reported_for_every_line() {
+        let line = assistant("m1", 3, serde_json::json!([{ "type": "text", "text": "partial" }]));
+        let seen = std::sync::Mutex::new(Vec::new());
+        let report = |p: &StreamProgress| seen.lock().unwrap().push(p.clone());
+        let reply = run_claude("prompt", 10, None, Permission::ReadOnly, None, &fake(&line), Some(&report))
+            .await
+            .unwrap();
+        // Without a result event the streamed text is the answer
+        assert_eq!(reply.text, "partial");
+        let seen = seen.into_inner().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!((seen[0].text.as_deref(), seen[0].output_tokens), (Some("partial"), 3));
+    }
+}
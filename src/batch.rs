@@ -0,0 +1,268 @@
+//! Batch files: many tasks, each run as its own council, and the index report linking their runs.
+
+use crate::config::Config;
+use crate::report::format_tokens;
+use crate::run::{MemberStatus, Run};
+use std::path::Path;
+
+/// One task of a batch file, with its settings resolved.
+pub struct BatchEntry {
+    /// Short name used for the entry's report file and progress lines
+    pub name: String,
+    pub task: String,
+    pub config: Config,
+}
+
+/// How a batch entry ended: the finished run, or why it could not run.
+pub struct BatchResult {
+    pub name: String,
+    pub task: String,
+    /// The run's report file, relative to the index
+    pub report: Option<String>,
+    pub outcome: Result<Run, String>,
+}
+
+/// A YAML value as the TOML value config files would hold.
+fn yaml_to_toml(value: &serde_yaml::Value) -> Result<toml::Value, String> {
+    Ok(match value {
+        serde_yaml::Value::Bool(b) => toml::Value::Boolean(*b),
+        serde_yaml::Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None => toml::Value::Float(n.as_f64().unwrap_or_default()),
+        },
+        serde_yaml::Value::String(s) => toml::Value::String(s.clone()),
+        serde_yaml::Value::Sequence(items) => {
+            toml::Value::Array(items.iter().map(yaml_to_toml).collect::<Result<_, _>>()?)
+        }
+        serde_yaml::Value::Mapping(map) => toml::Value::Table(yaml_table(map)?),
+        serde_yaml::Value::Null => return Err("must not be empty".to_string()),
+        serde_yaml::Value::Tagged(tagged) => yaml_to_toml(&tagged.value)?,
+    })
+}
+
+fn yaml_table(map: &serde_yaml::Mapping) -> Result<toml::Table, String> {
+    let mut table = toml::Table::new();
+    for (key, value) in map {
+        let key = key.as_str().ok_or("keys must be strings")?;
+        table.insert(key.to_string(), yaml_to_toml(value).map_err(|e| format!("{}: {}", key, e))?);
+    }
+    Ok(table)
+}
+
+/// A file-name-safe name from the start of a task.
+fn slug(task: &str) -> String {
+    let words: Vec<String> = task
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .take(5)
+        .map(str::to_lowercase)
+        .collect();
+    if words.is_empty() {
+        "task".to_string()
+    } else {
+        words.join("-")
+    }
+}
+
+/// Read a batch file: a YAML list of entries, or a mapping with that list under `tasks` and
+/// settings shared by every entry under `defaults`. An entry is a `task`, or a mapping with a
+/// `task` and optionally a `name`, a `profile`, `files` to include, and any config file setting.
+/// Every entry is resolved like a run started here with `profile`, then its own settings are
+/// applied, with paths relative to the batch file.
+pub fn load_batch(path: &Path, profile: Option<&str>) -> Result<Vec<BatchEntry>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let doc: serde_yaml::Value =
+        serde_yaml::from_str(&text).map_err(|e| format!("Invalid batch file {}: {}", path.display(), e))?;
+    let invalid = |e: String| format!("Invalid batch file {}: {}", path.display(), e);
+
+    let (defaults, tasks) = match &doc {
+        serde_yaml::Value::Sequence(tasks) => (serde_yaml::Mapping::new(), tasks),
+        serde_yaml::Value::Mapping(map) => {
+            for key in map.keys() {
+                if !matches!(key.as_str(), Some("defaults" | "tasks")) {
+                    let key = key.as_str().unwrap_or("?");
+                    return Err(invalid(format!("unknown key '{}' (expected defaults, tasks)", key)));
+                }
+            }
+            let defaults = match map.get("defaults") {
+                Some(serde_yaml::Value::Mapping(d)) => d.clone(),
+                Some(_) => return Err(invalid("defaults must be a mapping".to_string())),
+                None => serde_yaml::Mapping::new(),
+            };
+            match map.get("tasks") {
+                Some(serde_yaml::Value::Sequence(tasks)) => (defaults, tasks),
+                _ => return Err(invalid("tasks must be a list".to_string())),
+            }
+        }
+        _ => return Err(invalid("expected a list of tasks".to_string())),
+    };
+    if tasks.is_empty() {
+        return Err(invalid("no tasks".to_string()));
+    }
+
+    let mut entries = Vec::new();
+    for (i, item) in tasks.iter().enumerate() {
+        let label = format!("batch file {} entry {}", path.display(), i + 1);
+        let invalid = |e: String| format!("Invalid {}: {}", label, e);
+
+        let mut settings = defaults.clone();
+        match item {
+            serde_yaml::Value::String(task) => {
+                settings.insert("task".into(), task.as_str().into());
+            }
+            serde_yaml::Value::Mapping(map) => settings.extend(map.clone()),
+            _ => return Err(invalid("expected a task or a mapping".to_string())),
+        }
+
+        let mut take_string = |key: &str| match settings.remove(key) {
+            Some(serde_yaml::Value::String(s)) => Ok(Some(s)),
+            Some(_) => Err(invalid(format!("{} must be a string", key))),
+            None => Ok(None),
+        };
+        let task = take_string("task")?
+            .filter(|t| !t.trim().is_empty())
+            .ok_or_else(|| invalid("task is required".to_string()))?;
+        let name = take_string("name")?;
+        let entry_profile = take_string("profile")?;
+        if let Some(files) = settings.remove("files") {
+            settings.insert("file".into(), files);
+        }
+
+        let table = yaml_table(&settings).map_err(invalid)?;
+        let mut config = Config::resolve(entry_profile.as_deref().or(profile))?;
+        config.apply_overrides(&table, &label, path.parent())?;
+        // Each entry's report is written next to the index instead
+        config.report = None;
+
+        let name = format!("{:02}-{}", i + 1, slug(name.as_deref().unwrap_or(&task)));
+        entries.push(BatchEntry { name, task: task.trim().to_string(), config });
+    }
+    Ok(entries)
+}
+
+/// The index report of a batch: one row per entry linking to its run's report.
+pub fn render_batch_index(source: &Path, results: &[BatchResult]) -> String {
+    let mut md = String::new();
+    md.push_str("# Council Batch Report\n\n");
+    md.push_str(&format!("Tasks from `{}`.\n\n", source.display()));
+    md.push_str("| Entry | Run | Members | Synthesis | Time | Tokens | Cost |\n");
+    md.push_str("|---|---:|---|---|---:|---:|---:|\n");
+
+    let mut cost = 0.0;
+    for r in results {
+        match &r.outcome {
+            Ok(run) => {
+                let usage = run.total_usage();
+                cost += usage.cost_usd;
+                let succeeded = run.members.iter().filter(|m| m.status == MemberStatus::Success).count();
+                let synthesis = match &run.synthesis {
+                    Some(s) if s.text.is_ok() => "success",
+                    Some(_) => "failed",
+                    None => "skipped",
+                };
+                let entry = match &r.report {
+                    Some(report) => format!("[{}]({})", r.name, report),
+                    None => r.name.clone(),
+                };
+                md.push_str(&format!(
+                    "| {} | {} | {}/{} succeeded | {} | {:.1}s | {} | ${:.4} |\n",
+                    entry,
+                    run.id.map(|id| format!("#{}", id)).unwrap_or_else(|| "-".to_string()),
+                    succeeded,
+                    run.members.len(),
+                    synthesis,
+                    run.total_elapsed.as_secs_f64(),
+                    format_tokens(usage.total_tokens()),
+                    usage.cost_usd
+                ));
+            }
+            Err(e) => md.push_str(&format!("| {} | - | not run: {} | | | | |\n", r.name, e.replace('|', "\\|"))),
+        }
+    }
+    md.push_str(&format!("\n**Total cost:** ${:.4}\n\n", cost));
+
+    md.push_str("## Tasks\n\n");
+    for r in results {
+        md.push_str(&format!("### {}\n\n{}\n\n", r.name, r.task));
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// `yaml` written as a batch file in a fresh directory.
+    fn batch_file(test: &str, yaml: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("council-batch-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("batch.yaml");
+        std::fs::write(&path, yaml).unwrap();
+        path
+    }
+
+    fn load(test: &str, yaml: &str) -> Result<Vec<BatchEntry>, String> {
+        let path = batch_file(test, yaml);
+        let entries = load_batch(&path, None);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        entries
+    }
+
+    #[test]
+    fn list_of_tasks_is_named_after_each_task() {
+        let entries = load("list", "- Review the auth module for injection bugs\n- '  ?!  '\n").unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["01-review-the-auth-module-for", "02-task"]);
+        assert_eq!(entries[1].task, "?!");
+    }
+
+    #[test]
+    fn entries_layer_their_settings_over_the_defaults() {
+        let entries = load(
+            "defaults",
+            r#"
+defaults:
+  num: 4
+  model: sonnet
+  report: everything.md
+tasks:
+  - Check the parser
+  - task: Check the cache
+    name: Cache pass
+    num: 2
+    files: [docs/notes.md]
+"#,
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].config.num, entries[0].config.model.as_deref()), (4, Some("sonnet")));
+        assert_eq!(entries[1].name, "02-cache-pass");
+        assert_eq!(entries[1].task, "Check the cache");
+        assert_eq!((entries[1].config.num, entries[1].config.model.as_deref()), (2, Some("sonnet")));
+        assert!(entries[1].config.file[0].ends_with("docs/notes.md"));
+        assert!(entries[1].config.file[0].starts_with(std::env::temp_dir()));
+        assert!(entries.iter().all(|e| e.config.report.is_none()));
+    }
+
+    #[test]
+    fn malformed_batch_files_are_rejected() {
+        let cases = [
+            ("scalar", "just a task", "expected a list of tasks"),
+            ("empty", "[]", "no tasks"),
+            ("top-key", "tasks: [a]\nsettings: {}", "unknown key 'settings'"),
+            ("no-list", "tasks: a", "tasks must be a list"),
+            ("bad-defaults", "defaults: [num]\ntasks: [a]", "defaults must be a mapping"),
+            ("no-task", "- name: nameless", "entry 1: task is required"),
+            ("blank-task", "- a\n- task: '  '", "entry 2: task is required"),
+            ("number-task", "- task: 5", "task must be a string"),
+            ("nested", "- [a, b]", "expected a task or a mapping"),
+            ("null", "- task: a\n  model:", "model: must not be empty"),
+        ];
+        for (test, yaml, expected) in cases {
+            let err = load(test, yaml).err().unwrap_or_else(|| panic!("{} was accepted", test));
+            assert!(err.contains(expected), "{}: {}", test, err);
+        }
+    }
+}
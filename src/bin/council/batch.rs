@@ -0,0 +1,174 @@
+//! Several councils sharing the terminal: `council batch` runs every task of a batch file and
+//! writes an index report; `--per-dir` runs a council per directory, then synthesizes them.
+
+use crate::frontend::{watch_council, ViewOptions};
+use colored::*;
+use council::{
+    backend_installed, combine_runs, discover_dirs, load_batch, new_run, render_batch_index, render_report,
+    scoped_task, BatchEntry, BatchResult, Budget, Config, Council, Run,
+};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Run one entry, writing its report into `out` if given. With a shared `budget`, the entry
+/// spends from it instead of its own, and does not start once it is spent.
+async fn run_entry(entry: BatchEntry, out: Option<&Path>, budget: Option<&Budget>) -> BatchResult {
+    let mut report = None;
+    let outcome = async {
+        if let Some(reason) = budget.and_then(Budget::exceeded) {
+            return Err(format!("not started, budget exceeded ({})", reason));
+        }
+        if !backend_installed(&entry.config.backend) {
+            return Err(format!("'{}' CLI not found in PATH", entry.config.backend.command));
+        }
+        let run = new_run(&entry.config, entry.task.clone())?;
+        let view = ViewOptions { label: Some(entry.name.clone()), ..ViewOptions::default() };
+        let mut council = Council::from_config(&entry.config, run);
+        if let Some(budget) = budget {
+            council.budget = budget.clone();
+        }
+        let run = watch_council(council, &view).await?;
+        if let Some(out) = out {
+            let file = format!("{}.md", entry.name);
+            let path = out.join(&file);
+            std::fs::write(&path, render_report(&run))
+                .map_err(|e| format!("Cannot write report {}: {}", path.display(), e))?;
+            report = Some(file);
+        }
+        Ok(run)
+    }
+    .await;
+
+    match &outcome {
+        Ok(run) => println!(
+            "{} {}: {}, ${:.4}{}",
+            "[Done]".green(),
+            entry.name.bold(),
+            run.id.map(|id| format!("run #{}", id)).unwrap_or_else(|| "not saved".to_string()),
+            run.total_usage().cost_usd,
+            match (&report, out) {
+                (Some(file), Some(out)) => format!(" ({})", out.join(file).display()),
+                _ => String::new(),
+            }
+        ),
+        Err(e) => println!("{} {}: {}", "[Failed]".red(), entry.name.bold(), e),
+    }
+    BatchResult { name: entry.name, task: entry.task, report, outcome }
+}
+
+/// Run `entries`, at most `jobs` at once, returning their results in order. Runs hold the
+/// history database, which is not `Sync`, so they all stay on this thread.
+async fn run_entries(
+    entries: Vec<BatchEntry>,
+    jobs: usize,
+    out: Option<&Path>,
+    budget: Option<&Budget>,
+) -> Vec<BatchResult> {
+    let total = entries.len();
+    let queue = Rc::new(RefCell::new(entries.into_iter().enumerate().collect::<VecDeque<_>>()));
+    let results = Rc::new(RefCell::new(Vec::with_capacity(total)));
+    let local = tokio::task::LocalSet::new();
+    local
+        .run_until(async {
+            let workers: Vec<_> = (0..jobs.max(1).min(total))
+                .map(|_| {
+                    let (queue, results, out) = (queue.clone(), results.clone(), out.map(Path::to_path_buf));
+                    let budget = budget.cloned();
+                    tokio::task::spawn_local(async move {
+                        loop {
+                            let Some((i, entry)) = queue.borrow_mut().pop_front() else { return };
+                            let result = run_entry(entry, out.as_deref(), budget.as_ref()).await;
+                            results.borrow_mut().push((i, result));
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                let _ = worker.await;
+            }
+        })
+        .await;
+
+    // Results follow the order of the entries, not completion order
+    let mut results = results.take();
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+/// Run every entry of the batch file at `path`, at most `jobs` at once, then write the index of
+/// their reports to `out`.
+pub async fn run_batch(path: &Path, jobs: usize, out: &Path, profile: Option<&str>) -> Result<(), String> {
+    let entries = load_batch(path, profile)?;
+    std::fs::create_dir_all(out).map_err(|e| format!("Cannot create {}: {}", out.display(), e))?;
+
+    let total = entries.len();
+    println!(
+        "{} {} tasks from {}, {} at a time\n",
+        "Batch:".cyan(),
+        total,
+        path.display(),
+        jobs.max(1).min(total)
+    );
+    let results = run_entries(entries, jobs, Some(out), None).await;
+
+    let index = out.join("index.md");
+    std::fs::write(&index, render_batch_index(path, &results))
+        .map_err(|e| format!("Cannot write index {}: {}", index.display(), e))?;
+    println!("\n  {}: {}", "Index".cyan(), index.display());
+
+    let failed = results.iter().filter(|r| r.outcome.is_err()).count();
+    if failed > 0 {
+        return Err(format!("{} of {} tasks could not run", failed, total));
+    }
+    Ok(())
+}
+
+/// Review every directory `depth` levels down with its own council, `jobs` at a time, and return
+/// the council that synthesizes their syntheses into a repo-wide plan. `--max-cost` and
+/// `--max-tokens` cover all the directory councils together.
+pub async fn fan_out(config: &Config, task: &str, depth: usize, jobs: usize) -> Result<Council, String> {
+    let root = std::env::current_dir().map_err(|e| format!("Cannot read current directory: {}", e))?;
+    let dirs = discover_dirs(&root, depth);
+    if dirs.is_empty() {
+        return Err(format!("No directories with files under {}", root.display()));
+    }
+    println!(
+        "{} {} directories, {} at a time: {}\n",
+        "Per-directory review:".cyan(),
+        dirs.len(),
+        jobs.max(1).min(dirs.len()),
+        dirs.join(", ")
+    );
+
+    let mut dir_config = config.clone();
+    dir_config.report = None;
+    let entries = dirs
+        .iter()
+        .map(|dir| BatchEntry { name: dir.clone(), task: scoped_task(task, dir, &dirs), config: dir_config.clone() })
+        .collect();
+    let mut budget = Budget::new(config.max_cost, config.max_tokens);
+    budget.covers_synthesis = true;
+    let results = run_entries(entries, jobs, None, Some(&budget)).await;
+
+    let mut runs: Vec<(String, &Run)> = Vec::new();
+    for r in &results {
+        match &r.outcome {
+            Ok(run) if run.synthesis.as_ref().is_some_and(|s| s.text.is_ok()) => runs.push((r.name.clone(), run)),
+            _ => eprintln!("{} {}: no synthesis, left out of the repository synthesis", "Warning:".yellow().bold(), r.name),
+        }
+    }
+    println!();
+    let run = combine_runs(config, task.to_string(), &runs)?;
+
+    // The directory syntheses must not be replaced by cached member outputs
+    let mut top_config = config.clone();
+    top_config.cache = false;
+    top_config.refresh = false;
+    let mut council = Council::from_config(&top_config, run);
+    // The repo-wide synthesis runs over whatever finished, and counts against the same budget
+    budget.covers_synthesis = false;
+    council.budget = budget;
+    Ok(council)
+}
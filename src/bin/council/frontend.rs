@@ -0,0 +1,277 @@
+//! Front-ends for a live run, all fed by the council's events: scrolling lines, the full-screen
+//! TUI, JSON lines, labeled one-liners (batches), nothing at all (MCP), or an HTTP job's status
+//! and event stream.
+
+use crate::http::Job;
+use crate::render::{
+    print_analyses, print_footer, print_header, print_member_status, print_members_done, print_synthesis,
+    print_synthesis_started, LiveBoard,
+};
+use crate::tui::Tui;
+use colored::*;
+use council::{event_json, Council, Event, Run};
+use ratatui::crossterm::event::Event as TermEvent;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How a live run is displayed.
+#[derive(Clone, Default)]
+pub struct ViewOptions {
+    /// Print every member's analysis, not just the synthesis
+    pub all: bool,
+    /// Member whose text is streamed as it arrives
+    pub follow: Option<usize>,
+    /// Full-screen TUI instead of scrolling lines
+    pub tui: bool,
+    /// Print each event as a line of JSON instead
+    pub json: bool,
+    /// Print one labeled line per finished member and synthesis, for runs sharing the terminal
+    pub label: Option<String>,
+    /// Print nothing (the MCP server's stdout carries the protocol)
+    pub quiet: bool,
+    /// Report progress to an HTTP job, and stop when it is cancelled
+    pub job: Option<Arc<Job>>,
+}
+
+/// Where a live run's progress goes.
+enum Frontend {
+    /// The status board, and how many members were queued (none when re-synthesizing)
+    Lines(LiveBoard, usize),
+    /// The TUI, and warnings held back until it closes
+    Tui(Box<Tui>, Vec<String>),
+    Json,
+    Labeled(String),
+    Quiet,
+    Job(Arc<Job>),
+}
+
+/// Resolves when the HTTP job, if any, is cancelled.
+async fn job_cancelled(job: &Option<Arc<Job>>) {
+    match job {
+        Some(job) => job.cancel.notified().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// The next terminal event, or never when there is no TUI to read keys for.
+async fn next_key(keys: &mut Option<mpsc::UnboundedReceiver<TermEvent>>) -> Option<TermEvent> {
+    match keys {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn print_warning(message: &str) {
+    eprintln!("{} {}", "Warning:".yellow().bold(), message);
+}
+
+impl Frontend {
+    fn new(run: &Run, view: &ViewOptions) -> (Frontend, Option<mpsc::UnboundedReceiver<TermEvent>>) {
+        if let Some(job) = &view.job {
+            return (Frontend::Job(job.clone()), None);
+        }
+        if view.json {
+            return (Frontend::Json, None);
+        }
+        if let Some(label) = &view.label {
+            return (Frontend::Labeled(label.clone()), None);
+        }
+        if view.quiet {
+            return (Frontend::Quiet, None);
+        }
+        print_header(run);
+        if view.tui {
+            match Tui::start(run, view.follow) {
+                Ok((tui, keys)) => return (Frontend::Tui(Box::new(tui), Vec::new()), Some(keys)),
+                Err(e) => print_warning(&format!("{} (showing plain output)", e)),
+            }
+        }
+        (Frontend::Lines(LiveBoard::new(view.follow), 0), None)
+    }
+
+    fn tick_period(&self) -> Duration {
+        match self {
+            Frontend::Tui(..) => Duration::from_millis(200),
+            _ => Duration::from_secs(1),
+        }
+    }
+
+    /// Show an event; `run` has already been brought up to date with it.
+    fn event(&mut self, event: Event, run: &Run, all: bool) {
+        match self {
+            Frontend::Lines(board, queued) => match event {
+                Event::MemberQueued(_) => *queued += 1,
+                Event::MemberStarted(id) => {
+                    if let Some(m) = run.members.get(id) {
+                        print_member_status(m);
+                        board.start(m);
+                    }
+                }
+                Event::MemberChunk(id, progress) => board.progress(id, &progress),
+                Event::MemberFinished(m) => {
+                    board.clear();
+                    board.finish(m.id);
+                    print_member_status(&m);
+                    board.draw();
+                }
+                Event::SandboxChanges(id, changes) => board.note(&format!(
+                    "{} Member #{} changed {} file(s) in its sandbox (discarded)",
+                    "[Sandbox]".yellow(),
+                    id + 1,
+                    changes.len()
+                )),
+                Event::MembersDone { .. } => {
+                    board.clear();
+                    if *queued > 0 {
+                        print_members_done(run);
+                    }
+                    if all {
+                        print_analyses(run);
+                    }
+                }
+                Event::SynthesisStarted => print_synthesis_started(),
+                Event::SynthesisFinished(s) => print_synthesis(&s),
+                Event::Warning(w) => {
+                    board.clear();
+                    print_warning(&w);
+                    board.draw();
+                }
+                Event::SynthesisChunk(_) | Event::RunFinished { .. } => {}
+            },
+            Frontend::Tui(tui, warnings) => {
+                match event {
+                    Event::MemberStarted(id) => tui.member_started(id),
+                    Event::MemberChunk(id, progress) => tui.progress(Some(id), &progress),
+                    Event::MemberFinished(m) => tui.member_finished(&m),
+                    Event::SynthesisStarted => tui.synthesis_started(),
+                    Event::SynthesisChunk(progress) => tui.progress(None, &progress),
+                    Event::SynthesisFinished(s) => tui.synthesis_finished(&s),
+                    Event::Warning(w) => warnings.push(w),
+                    _ => {}
+                }
+                tui.draw();
+            }
+            Frontend::Json => println!("{}", event_json(&event, run)),
+            Frontend::Labeled(label) => match event {
+                Event::MemberFinished(m) => {
+                    print!("{} ", label.bold());
+                    print_member_status(&m);
+                }
+                Event::SynthesisFinished(s) => {
+                    let status = if s.text.is_ok() { "[Synthesized]".green() } else { "[Synthesis failed]".red() };
+                    println!("{} {} ({:.1}s)", label.bold(), status, s.elapsed.as_secs_f64());
+                }
+                Event::Warning(w) => print_warning(&format!("{}: {}", label, w)),
+                _ => {}
+            },
+            Frontend::Quiet => {
+                if let Event::Warning(w) = event {
+                    print_warning(&w);
+                }
+            }
+            Frontend::Job(job) => {
+                if let Event::Warning(w) = &event {
+                    print_warning(w);
+                }
+                job.event(&event, run);
+            }
+        }
+    }
+
+    fn tick(&mut self) {
+        match self {
+            Frontend::Lines(board, _) => board.tick(),
+            Frontend::Tui(tui, _) => tui.draw(),
+            Frontend::Json | Frontend::Labeled(_) | Frontend::Quiet | Frontend::Job(_) => {}
+        }
+    }
+
+    /// Handle a terminal event; true when the user asked to quit.
+    fn key(&mut self, event: TermEvent) -> bool {
+        match self {
+            Frontend::Lines(..) | Frontend::Json | Frontend::Labeled(_) | Frontend::Quiet | Frontend::Job(_) => false,
+            Frontend::Tui(tui, _) => tui.key(event),
+        }
+    }
+
+    /// Keep the TUI up until the user quits.
+    async fn wait_for_exit(&mut self, keys: &mut Option<mpsc::UnboundedReceiver<TermEvent>>) {
+        if let Frontend::Tui(tui, _) = self {
+            tui.banner = "Done, press q to exit".to_string();
+            tui.draw();
+            while let Some(event) = next_key(keys).await {
+                if tui.key(event) {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Leave the TUI, if any, printing what plain output would have shown so it stays in the
+    /// scrollback, then print the timing and cost footer.
+    fn close(self, run: &Run, all: bool) {
+        match self {
+            Frontend::Json | Frontend::Labeled(_) | Frontend::Quiet | Frontend::Job(_) => return,
+            Frontend::Tui(tui, warnings) => {
+                drop(tui);
+                for w in &warnings {
+                    print_warning(w);
+                }
+                print_members_done(run);
+                if all {
+                    print_analyses(run);
+                }
+                if let Some(s) = &run.synthesis {
+                    print_synthesis(s);
+                }
+            }
+            Frontend::Lines(..) => {}
+        }
+        print_footer(run);
+    }
+}
+
+/// Execute `council`, showing its events as `view` asks. Quitting the TUI or cancelling the
+/// HTTP job cancels the council.
+pub async fn watch_council(mut council: Council, view: &ViewOptions) -> Result<Run, String> {
+    let mut events = council.events();
+    let cancel = council.cancel_handle();
+    let (mut frontend, mut keys) = Frontend::new(&council.run, view);
+    let mut mirror = council.run.clone();
+
+    let show = async {
+        let mut ticker = tokio::time::interval(frontend.tick_period());
+        let mut quit = false;
+        loop {
+            tokio::select! {
+                biased;
+                Some(key) = next_key(&mut keys), if !quit => {
+                    if frontend.key(key) {
+                        cancel.cancel();
+                        quit = true;
+                    }
+                }
+                _ = job_cancelled(&view.job), if !quit => {
+                    cancel.cancel();
+                    quit = true;
+                }
+                event = events.recv() => {
+                    let Some(event) = event else { break };
+                    mirror.apply(&event);
+                    frontend.event(event, &mirror, view.all);
+                }
+                _ = ticker.tick() => frontend.tick(),
+            }
+        }
+        quit
+    };
+    let (run, quit) = tokio::join!(council.execute(), show);
+    let run = run?;
+
+    if !quit {
+        frontend.wait_for_exit(&mut keys).await;
+    }
+    frontend.close(&run, view.all);
+    Ok(run)
+}
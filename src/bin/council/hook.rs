@@ -0,0 +1,119 @@
+//! `council hook`: install a git hook that has a quick council review changes before they are
+//! committed or pushed, and the review that hook runs.
+
+use crate::frontend::{watch_council, ViewOptions};
+use crate::render::format_finding;
+use colored::*;
+use council::{
+    backend_installed, blocking_findings, changes_to_review, hook_task, install_hook, new_run, uninstall_hook,
+    BlockPolicy, Config, Council, HookKind,
+};
+use std::io::Read;
+
+/// `s` quoted for a POSIX shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Install a `kind` hook that reviews with `profile` and blocks as `block` says.
+pub fn install(kind: HookKind, profile: &str, block: BlockPolicy, force: bool) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Cannot locate the council binary: {}", e))?;
+    let command = format!(
+        "{} hook run --hook {} --profile {} --block {}",
+        shell_quote(&exe.to_string_lossy()),
+        kind.as_str(),
+        shell_quote(profile),
+        block.as_str()
+    );
+    let path = install_hook(kind, &command, force)?;
+    println!("{} {} hook: {}", "Installed".green(), kind.as_str(), path.display());
+    println!(
+        "  Each {} is reviewed with the {} profile; {}.",
+        kind.action(),
+        profile,
+        match block {
+            BlockPolicy::P0 => "P0 findings block it",
+            BlockPolicy::P1 => "P0 and P1 findings block it",
+            BlockPolicy::Never => "nothing blocks it",
+        }
+    );
+    println!(
+        "  Skip once with `git {} --no-verify`; remove with `council hook uninstall --hook {}`.",
+        kind.action(),
+        kind.as_str()
+    );
+    Ok(())
+}
+
+/// Remove the `kind` hook council installed.
+pub fn uninstall(kind: HookKind) -> Result<(), String> {
+    let path = uninstall_hook(kind)?;
+    println!("{} {} hook: {}", "Removed".green(), kind.as_str(), path.display());
+    if path.exists() {
+        println!("  Restored the {} hook it had replaced.", kind.as_str());
+    }
+    Ok(())
+}
+
+/// The review an installed hook runs: a council over the changes, failing when `block` says
+/// its synthesis must stop the commit or push. When the council itself cannot run or finish,
+/// git is let through with a warning rather than blocked.
+pub async fn run(kind: HookKind, profile: &str, block: BlockPolicy) -> Result<(), String> {
+    let mut pushed = String::new();
+    if kind == HookKind::PrePush {
+        std::io::stdin()
+            .read_to_string(&mut pushed)
+            .map_err(|e| format!("Cannot read the pushed refs: {}", e))?;
+    }
+    let diff = changes_to_review(kind, &pushed)?;
+    if diff.trim().is_empty() {
+        println!("{} nothing to review", "council:".cyan());
+        return Ok(());
+    }
+
+    let mut config = Config::resolve(Some(profile))?;
+    config.tui = false;
+    if !backend_installed(&config.backend) {
+        let message = format!("'{}' CLI not found in PATH; not reviewing this {}", config.backend.command, kind.action());
+        eprintln!("{} {}", "Warning:".yellow().bold(), message);
+        return Ok(());
+    }
+    println!(
+        "{} reviewing this {} with the {} profile (blocks on: {})\n",
+        "council:".cyan(),
+        kind.action(),
+        profile,
+        block.as_str()
+    );
+    let run = new_run(&config, hook_task(kind, &diff))?;
+    let run = match watch_council(Council::from_config(&config, run), &ViewOptions::default()).await {
+        Ok(run) => run,
+        Err(e) => {
+            eprintln!("{} {}; not blocking this {}", "Warning:".yellow().bold(), e, kind.action());
+            return Ok(());
+        }
+    };
+    if let Some(id) = run.id {
+        println!("\n  {}: council show {}", "Saved as run".cyan(), id);
+    }
+
+    if block != BlockPolicy::Never && run.synthesis.as_ref().is_none_or(|s| s.text.is_err()) {
+        eprintln!("{} no synthesis to check; not blocking this {}", "Warning:".yellow().bold(), kind.action());
+        return Ok(());
+    }
+    let blocking = blocking_findings(&run, block);
+    if blocking.is_empty() {
+        return Ok(());
+    }
+    eprintln!();
+    for f in &blocking {
+        eprintln!("  {} {}", "-".red(), format_finding(f));
+    }
+    eprintln!();
+    Err(format!(
+        "{} blocking finding(s) stop this {}; fix them, or skip the review with `git {} --no-verify`",
+        blocking.len(),
+        kind.action(),
+        kind.action()
+    ))
+}
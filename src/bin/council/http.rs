@@ -0,0 +1,536 @@
+//! HTTP server (`council serve --http`: a bounded job queue with SSE progress).
+
+use crate::frontend::{watch_council, ViewOptions};
+use crate::mcp::council_config;
+use colored::*;
+use council::{backend_installed, event_json, new_run, render_report, run_json, Council, Event, Run};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// How long a finished job's status and report stay available (its run stays in history).
+const FINISHED_JOB_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Finished jobs kept at most, newest first, however recent.
+const MAX_FINISHED_JOBS: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum JobStatus {
+    Queued,
+    Running,
+    Finished,
+    Cancelled,
+    Failed,
+}
+
+impl JobStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Finished => "finished",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    fn is_done(self) -> bool {
+        matches!(self, JobStatus::Finished | JobStatus::Cancelled | JobStatus::Failed)
+    }
+}
+
+/// A member of a job's run, as reported by the status endpoint.
+struct JobMember {
+    name: String,
+    /// A `MemberStatus`, or "running"
+    status: &'static str,
+    elapsed: Option<Duration>,
+    bytes: usize,
+    output_tokens: u64,
+    tool: Option<String>,
+}
+
+struct JobState {
+    status: JobStatus,
+    error: Option<String>,
+    run_id: Option<i64>,
+    members: Vec<JobMember>,
+    /// "pending", "running", "success", "failed" or "skipped"
+    synthesis: &'static str,
+    /// Markdown and JSON reports, once the run is done
+    report: Option<(String, serde_json::Value)>,
+    /// When the job finished, was cancelled or failed
+    done_at: Option<Instant>,
+}
+
+/// A run submitted over HTTP, shared between its handlers and the worker running it.
+pub struct Job {
+    id: u64,
+    task: String,
+    request: serde_json::Value,
+    state: std::sync::Mutex<JobState>,
+    /// (event, JSON data) for SSE subscribers; dropped when the job is done, ending their streams
+    events: std::sync::Mutex<Option<tokio::sync::broadcast::Sender<(&'static str, String)>>>,
+    pub cancel: tokio::sync::Notify,
+    cancel_requested: std::sync::atomic::AtomicBool,
+}
+
+impl Job {
+    fn emit(&self, event: &'static str, data: serde_json::Value) {
+        if let Some(tx) = self.events.lock().unwrap().as_ref() {
+            let _ = tx.send((event, data.to_string()));
+        }
+    }
+
+    fn status(&self) -> JobStatus {
+        self.state.lock().unwrap().status
+    }
+
+    fn snapshot(&self) -> serde_json::Value {
+        let state = self.state.lock().unwrap();
+        let members: Vec<serde_json::Value> = state
+            .members
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                serde_json::json!({
+                    "id": i + 1,
+                    "name": m.name,
+                    "status": m.status,
+                    "elapsed_secs": m.elapsed.map(|d| d.as_secs_f64()),
+                    "bytes": m.bytes,
+                    "output_tokens": m.output_tokens,
+                    "tool": m.tool,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "id": self.id,
+            "task": self.task,
+            "status": state.status.as_str(),
+            "error": state.error,
+            "run_id": state.run_id,
+            "members": members,
+            "synthesis": state.synthesis,
+        })
+    }
+
+    fn set_status(&self, status: JobStatus, error: Option<String>) {
+        Self::update(&mut self.state.lock().unwrap(), status, error.clone());
+        self.announce(status, error);
+    }
+
+    /// Move the job from `from` to `to` in one step, returning whether it was in `from`, so a
+    /// cancel and the worker picking the job up cannot both win.
+    fn transition(&self, from: JobStatus, to: JobStatus) -> bool {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.status != from {
+                return false;
+            }
+            Self::update(&mut state, to, None);
+        }
+        self.announce(to, None);
+        true
+    }
+
+    fn update(state: &mut JobState, status: JobStatus, error: Option<String>) {
+        state.status = status;
+        state.error = error;
+        if status.is_done() {
+            state.done_at = Some(Instant::now());
+        }
+    }
+
+    /// Tell SSE subscribers the job is now `status`, ending their streams once it is done.
+    fn announce(&self, status: JobStatus, error: Option<String>) {
+        self.emit("status", serde_json::json!({ "status": status.as_str(), "error": error }));
+        if status.is_done() {
+            self.emit("done", self.snapshot());
+            self.events.lock().unwrap().take();
+        }
+    }
+
+    /// Track an event from the job's council and pass it on to SSE subscribers.
+    pub fn event(&self, event: &Event, run: &Run) {
+        {
+            let mut state = self.state.lock().unwrap();
+            match event {
+                Event::MemberStarted(id) => {
+                    if let Some(member) = state.members.get_mut(*id) {
+                        member.status = "running";
+                    }
+                }
+                Event::MemberChunk(id, progress) => {
+                    if let Some(member) = state.members.get_mut(*id) {
+                        member.bytes = progress.bytes;
+                        member.output_tokens = progress.output_tokens;
+                        member.tool = progress.tool.clone();
+                    }
+                }
+                Event::MemberFinished(m) => {
+                    if let Some(member) = state.members.get_mut(m.id) {
+                        member.status = m.status.as_str();
+                        member.elapsed = Some(m.elapsed);
+                        member.tool = None;
+                    }
+                }
+                Event::SynthesisStarted => state.synthesis = "running",
+                Event::SynthesisFinished(s) => state.synthesis = if s.text.is_ok() { "success" } else { "failed" },
+                _ => {}
+            }
+        }
+        // Chunk text would repeat the whole transcript to every subscriber; totals are enough
+        let mut data = event_json(event, run);
+        if let Some(data) = data.as_object_mut() {
+            data.remove("text");
+        }
+        self.emit(event.name(), data);
+    }
+}
+
+struct HttpState {
+    jobs: std::sync::Mutex<HashMap<u64, std::sync::Arc<Job>>>,
+    next_id: std::sync::atomic::AtomicU64,
+    queue: mpsc::Sender<std::sync::Arc<Job>>,
+}
+
+type HttpResponse = (axum::http::StatusCode, axum::Json<serde_json::Value>);
+
+fn http_error(status: axum::http::StatusCode, message: impl Into<String>) -> HttpResponse {
+    (status, axum::Json(serde_json::json!({ "error": message.into() })))
+}
+
+/// Forget finished jobs older than `FINISHED_JOB_TTL`, and all but the newest
+/// `MAX_FINISHED_JOBS`, so the server's memory does not grow with every job it has run.
+fn prune_jobs(jobs: &mut HashMap<u64, std::sync::Arc<Job>>) {
+    let mut finished: Vec<(Instant, u64)> = jobs
+        .values()
+        .filter_map(|job| job.state.lock().unwrap().done_at.map(|at| (at, job.id)))
+        .collect();
+    finished.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+    for (i, (at, id)) in finished.into_iter().enumerate() {
+        if i >= MAX_FINISHED_JOBS || at.elapsed() > FINISHED_JOB_TTL {
+            jobs.remove(&id);
+        }
+    }
+}
+
+fn find_job(state: &HttpState, id: u64) -> Result<std::sync::Arc<Job>, HttpResponse> {
+    state
+        .jobs
+        .lock()
+        .unwrap()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| http_error(axum::http::StatusCode::NOT_FOUND, format!("No job with id {}", id)))
+}
+
+/// Serve the job API on `addr`, running at most `jobs` councils at once with up to `queue` more
+/// waiting. Runs hold the history database, which is not `Sync`, so workers stay on this thread.
+pub async fn serve_http(addr: &str, jobs: usize, queue: usize) -> Result<(), String> {
+    use axum::routing::get;
+
+    let (queue_tx, queue_rx) = mpsc::channel(queue.max(1));
+    let state = std::sync::Arc::new(HttpState {
+        jobs: std::sync::Mutex::new(HashMap::new()),
+        next_id: std::sync::atomic::AtomicU64::new(1),
+        queue: queue_tx,
+    });
+    let app = axum::Router::new()
+        .route("/jobs", get(http_list_jobs).post(http_create_job))
+        .route("/jobs/{id}", get(http_job_status).delete(http_cancel_job))
+        .route("/jobs/{id}/events", get(http_job_events))
+        .route("/jobs/{id}/report", get(http_job_report))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Cannot listen on {}: {}", addr, e))?;
+    eprintln!(
+        "{} http://{} ({} concurrent runs, {} queued)",
+        "Listening on".cyan(),
+        listener.local_addr().map(|a| a.to_string()).unwrap_or_else(|_| addr.to_string()),
+        jobs.max(1),
+        queue.max(1)
+    );
+
+    let queue_rx = std::rc::Rc::new(tokio::sync::Mutex::new(queue_rx));
+    let local = tokio::task::LocalSet::new();
+    local
+        .run_until(async move {
+            for _ in 0..jobs.max(1) {
+                tokio::task::spawn_local(http_worker(queue_rx.clone()));
+            }
+            axum::serve(listener, app).await.map_err(|e| format!("HTTP server failed: {}", e))
+        })
+        .await
+}
+
+async fn http_worker(queue: std::rc::Rc<tokio::sync::Mutex<mpsc::Receiver<std::sync::Arc<Job>>>>) {
+    loop {
+        let Some(job) = queue.lock().await.recv().await else { return };
+        if !job.transition(JobStatus::Queued, JobStatus::Running) {
+            continue; // cancelled while waiting
+        }
+
+        let prepared = council_config(&job.request).and_then(|config| {
+            if !backend_installed(&config.backend) {
+                return Err(format!("'{}' CLI not found in PATH", config.backend.command));
+            }
+            new_run(&config, job.task.clone()).map(|run| (config, run))
+        });
+        let (config, run) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                job.set_status(JobStatus::Failed, Some(e));
+                continue;
+            }
+        };
+        {
+            let mut state = job.state.lock().unwrap();
+            state.members = run
+                .members
+                .iter()
+                .map(|m| JobMember {
+                    name: m.name.clone(),
+                    status: m.status.as_str(),
+                    elapsed: None,
+                    bytes: 0,
+                    output_tokens: 0,
+                    tool: None,
+                })
+                .collect();
+            state.synthesis = if run.settings.synthesize { "pending" } else { "skipped" };
+        }
+
+        let view = ViewOptions { quiet: true, job: Some(job.clone()), ..ViewOptions::default() };
+        let run = match watch_council(Council::from_config(&config, run), &view).await {
+            Ok(run) => run,
+            Err(e) => {
+                job.set_status(JobStatus::Failed, Some(e));
+                continue;
+            }
+        };
+        {
+            let mut state = job.state.lock().unwrap();
+            state.run_id = run.id;
+            state.report = Some((render_report(&run), run_json(&run)));
+            if run.synthesis.is_none() {
+                state.synthesis = "skipped";
+            }
+        }
+        let status = if job.cancel_requested.load(std::sync::atomic::Ordering::Relaxed) {
+            JobStatus::Cancelled
+        } else {
+            JobStatus::Finished
+        };
+        job.set_status(status, None);
+    }
+}
+
+async fn http_list_jobs(
+    axum::extract::State(state): axum::extract::State<std::sync::Arc<HttpState>>,
+) -> axum::Json<serde_json::Value> {
+    let mut jobs: Vec<std::sync::Arc<Job>> = {
+        let mut jobs = state.jobs.lock().unwrap();
+        prune_jobs(&mut jobs);
+        jobs.values().cloned().collect()
+    };
+    jobs.sort_by_key(|j| std::cmp::Reverse(j.id));
+    let list: Vec<serde_json::Value> = jobs
+        .iter()
+        .map(|j| serde_json::json!({ "id": j.id, "task": j.task, "status": j.status().as_str() }))
+        .collect();
+    axum::Json(serde_json::json!({ "jobs": list }))
+}
+
+async fn http_create_job(
+    axum::extract::State(state): axum::extract::State<std::sync::Arc<HttpState>>,
+    axum::Json(request): axum::Json<serde_json::Value>,
+) -> HttpResponse {
+    let Some(task) = request["task"].as_str().map(str::trim).filter(|t| !t.is_empty()) else {
+        return http_error(axum::http::StatusCode::BAD_REQUEST, "A non-empty \"task\" is required");
+    };
+    // Settings are checked now so a bad request fails here rather than in the queue
+    if let Err(e) = council_config(&request) {
+        return http_error(axum::http::StatusCode::BAD_REQUEST, e);
+    }
+
+    let (events, _) = tokio::sync::broadcast::channel(1024);
+    let job = std::sync::Arc::new(Job {
+        id: state.next_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        task: task.to_string(),
+        request: request.clone(),
+        state: std::sync::Mutex::new(JobState {
+            status: JobStatus::Queued,
+            error: None,
+            run_id: None,
+            members: Vec::new(),
+            synthesis: "pending",
+            report: None,
+            done_at: None,
+        }),
+        events: std::sync::Mutex::new(Some(events)),
+        cancel: tokio::sync::Notify::new(),
+        cancel_requested: std::sync::atomic::AtomicBool::new(false),
+    });
+    if state.queue.try_send(job.clone()).is_err() {
+        return http_error(axum::http::StatusCode::SERVICE_UNAVAILABLE, "The job queue is full; try again later");
+    }
+    {
+        let mut jobs = state.jobs.lock().unwrap();
+        prune_jobs(&mut jobs);
+        jobs.insert(job.id, job.clone());
+    }
+    (axum::http::StatusCode::ACCEPTED, axum::Json(job.snapshot()))
+}
+
+async fn http_job_status(
+    axum::extract::State(state): axum::extract::State<std::sync::Arc<HttpState>>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+) -> HttpResponse {
+    match find_job(&state, id) {
+        Ok(job) => (axum::http::StatusCode::OK, axum::Json(job.snapshot())),
+        Err(e) => e,
+    }
+}
+
+async fn http_cancel_job(
+    axum::extract::State(state): axum::extract::State<std::sync::Arc<HttpState>>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+) -> HttpResponse {
+    let job = match find_job(&state, id) {
+        Ok(job) => job,
+        Err(e) => return e,
+    };
+    if job.transition(JobStatus::Queued, JobStatus::Cancelled) {
+        return (axum::http::StatusCode::ACCEPTED, axum::Json(job.snapshot()));
+    }
+    match job.status() {
+        // The worker marks the job cancelled once its members have been stopped
+        JobStatus::Running => {
+            job.cancel_requested.store(true, std::sync::atomic::Ordering::Relaxed);
+            job.cancel.notify_one();
+        }
+        _ => return http_error(axum::http::StatusCode::CONFLICT, format!("Job {} is already done", id)),
+    }
+    (axum::http::StatusCode::ACCEPTED, axum::Json(job.snapshot()))
+}
+
+async fn http_job_events(
+    axum::extract::State(state): axum::extract::State<std::sync::Arc<HttpState>>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+) -> Result<axum::response::Response, HttpResponse> {
+    use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+    use axum::response::IntoResponse;
+    use tokio_stream::StreamExt;
+
+    let job = find_job(&state, id)?;
+    // Subscribe before the snapshot so nothing between the two is missed
+    let rx = job.events.lock().unwrap().as_ref().map(|tx| tx.subscribe());
+    let first = match rx {
+        Some(_) => SseEvent::default().event("status").data(job.snapshot().to_string()),
+        None => SseEvent::default().event("done").data(job.snapshot().to_string()),
+    };
+    let live: std::pin::Pin<Box<dyn tokio_stream::Stream<Item = SseEvent> + Send>> = match rx {
+        Some(rx) => Box::pin(
+            tokio_stream::wrappers::BroadcastStream::new(rx)
+                .filter_map(|message| message.ok())
+                .map(|(event, data)| SseEvent::default().event(event).data(data)),
+        ),
+        None => Box::pin(tokio_stream::empty()),
+    };
+    let stream = tokio_stream::once(first).chain(live).map(Ok::<_, std::convert::Infallible>);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()).into_response())
+}
+
+async fn http_job_report(
+    axum::extract::State(state): axum::extract::State<std::sync::Arc<HttpState>>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+    axum::extract::Query(query): axum::extract::Query<HashMap<String, String>>,
+    headers: axum::http::HeaderMap,
+) -> Result<axum::response::Response, HttpResponse> {
+    use axum::response::IntoResponse;
+
+    let job = find_job(&state, id)?;
+    let Some((markdown, json)) = job.state.lock().unwrap().report.clone() else {
+        return Err(http_error(
+            axum::http::StatusCode::CONFLICT,
+            format!("Job {} is {}; the report is ready once it finishes", id, job.status().as_str()),
+        ));
+    };
+    let wants_json = match query.get("format").map(String::as_str) {
+        Some("json") => true,
+        Some("md") | Some("markdown") => false,
+        Some(other) => {
+            return Err(http_error(axum::http::StatusCode::BAD_REQUEST, format!("Unknown format '{}' (md or json)", other)))
+        }
+        None => headers
+            .get(axum::http::header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("application/json")),
+    };
+    Ok(if wants_json {
+        axum::Json(json).into_response()
+    } else {
+        ([(axum::http::header::CONTENT_TYPE, "text/markdown; charset=utf-8")], markdown).into_response()
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued_job() -> Job {
+        Job {
+            id: 1,
+            task: "Review".to_string(),
+            request: serde_json::json!({}),
+            state: std::sync::Mutex::new(JobState {
+                status: JobStatus::Queued,
+                error: None,
+                run_id: None,
+                members: Vec::new(),
+                synthesis: "pending",
+                report: None,
+                done_at: None,
+            }),
+            events: std::sync::Mutex::new(None),
+            cancel: tokio::sync::Notify::new(),
+            cancel_requested: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    #[test]
+    fn a_cancelled_job_cannot_start() {
+        let job = queued_job();
+        assert!(job.transition(JobStatus::Queued, JobStatus::Cancelled));
+        assert!(!job.transition(JobStatus::Queued, JobStatus::Running));
+        assert_eq!(job.status(), JobStatus::Cancelled);
+        assert!(job.state.lock().unwrap().done_at.is_some());
+    }
+
+    #[test]
+    fn a_started_job_cannot_be_cancelled_from_the_queue() {
+        let job = queued_job();
+        assert!(job.transition(JobStatus::Queued, JobStatus::Running));
+        assert!(!job.transition(JobStatus::Queued, JobStatus::Cancelled));
+        assert_eq!(job.status(), JobStatus::Running);
+    }
+
+    #[test]
+    fn only_one_of_racing_transitions_wins() {
+        let job = std::sync::Arc::new(queued_job());
+        let racers: Vec<_> = [JobStatus::Running, JobStatus::Cancelled]
+            .into_iter()
+            .map(|to| {
+                let job = job.clone();
+                std::thread::spawn(move || job.transition(JobStatus::Queued, to))
+            })
+            .collect();
+        let won = racers.into_iter().map(|r| r.join().unwrap()).filter(|won| *won).count();
+        assert_eq!(won, 1);
+    }
+}
@@ -0,0 +1,881 @@
+//! The `council` command line: run a council in the terminal, browse and resume saved runs,
+//! and serve councils over MCP or HTTP.
+
+mod batch;
+mod frontend;
+mod hook;
+mod http;
+mod mcp;
+mod render;
+mod tui;
+mod watch;
+
+use clap::{Parser, Subcommand};
+use colored::*;
+use council::{
+    backend_installed, load_synthesis_template, new_run, render_report, review_comments_json, Backend, BlockPolicy,
+    Budget, Config, Council, History, HookKind, Isolation, MemberOutput, MemberStatus, Permission, PromptTemplate, Run,
+    RunSettings, Usage,
+};
+use council::run::unix_now;
+use batch::{fan_out, run_batch};
+use frontend::{watch_council, ViewOptions};
+use http::serve_http;
+use mcp::serve_mcp;
+use watch::{watch, WatchOptions};
+use render::{print_config, print_history, print_run, print_run_diff};
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+#[derive(Parser, Debug)]
+#[command(name = "council")]
+#[command(about = "Spawn multiple Claude instances to analyze with orthogonal constraints")]
+#[command(args_conflicts_with_subcommands = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    /// Task description for the council to analyze ("-" reads from stdin)
+    task: Option<String>,
+
+    /// Read the task description from a file
+    #[arg(long, value_name = "PATH", conflicts_with = "task")]
+    task_file: Option<std::path::PathBuf>,
+
+    /// Apply a named profile from the config files (built-in: quick, deep)
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Number of council members (default: 5)
+    #[arg(short = 'n', long)]
+    num: Option<usize>,
+
+    /// Timeout per member in seconds (default: 600)
+    #[arg(short, long)]
+    timeout: Option<u64>,
+
+    /// Model to use (sonnet, opus, haiku)
+    #[arg(short, long)]
+    model: Option<String>,
+
+    /// Skip synthesis phase (synthesis runs by default)
+    #[arg(long, overrides_with = "synthesize")]
+    no_synthesize: bool,
+
+    /// Run synthesis even if a config file or profile turns it off
+    #[arg(long, overrides_with = "no_synthesize")]
+    synthesize: bool,
+
+    /// Tool permissions for council members (default: read-only)
+    #[arg(long, value_enum)]
+    permissions: Option<Permission>,
+
+    /// Tool permissions for the synthesis step (default: none)
+    #[arg(long, value_enum)]
+    synthesis_permissions: Option<Permission>,
+
+    /// Run each member in a throwaway copy of the repository (default: none)
+    #[arg(long, value_enum)]
+    isolate: Option<Isolation>,
+
+    /// Show all individual analyses (default: synthesis only)
+    #[arg(long, overrides_with = "no_all")]
+    all: bool,
+
+    /// Show only the synthesis even if a config file or profile sets `all`
+    #[arg(long, overrides_with = "all")]
+    no_all: bool,
+
+    /// Synthesis template: a built-in name (default, adr, review, verdict) or a file path
+    #[arg(long, value_name = "NAME|PATH")]
+    synthesis_template: Option<String>,
+
+    /// Member prompt template file; CONSTRAINT=PATH overrides a single lens (repeatable)
+    #[arg(long, value_name = "[CONSTRAINT=]PATH")]
+    member_template: Vec<String>,
+
+    /// Include a file's contents in every member's prompt (repeatable)
+    #[arg(short, long, value_name = "PATH")]
+    file: Vec<PathBuf>,
+
+    /// Stop the run once members have cost this many US dollars
+    #[arg(long, value_name = "USD")]
+    max_cost: Option<f64>,
+
+    /// Stop the run once members have used this many tokens
+    #[arg(long, value_name = "TOKENS")]
+    max_tokens: Option<u64>,
+
+    /// Save a Markdown report of the run (analyses, synthesis, timings and costs)
+    #[arg(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+
+    /// Reuse cached outputs of members with an identical prompt, model and repository state
+    #[arg(long, overrides_with = "no_cache")]
+    cache: bool,
+
+    /// Never read or write the member cache
+    #[arg(long, overrides_with = "cache", conflicts_with = "refresh")]
+    no_cache: bool,
+
+    /// Re-run every member and overwrite its cache entry
+    #[arg(long)]
+    refresh: bool,
+
+    /// Seed for constraint selection (reuse a run's seed to get the same roster)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Do not save this run to the history database
+    #[arg(long, overrides_with = "history")]
+    no_history: bool,
+
+    /// Save this run to the history database even if a config file turns history off
+    #[arg(long, overrides_with = "no_history")]
+    history: bool,
+
+    /// Stream one member's text to the terminal as it arrives (number or constraint name)
+    #[arg(long, value_name = "MEMBER")]
+    follow: Option<String>,
+
+    /// Show the run in a full-screen terminal UI
+    #[arg(long, overrides_with = "no_tui")]
+    tui: bool,
+
+    /// Print plain output even if a config file or profile sets `tui`
+    #[arg(long, overrides_with = "tui")]
+    no_tui: bool,
+
+    /// Print the run's progress as JSON lines, one event per line, instead of formatted output
+    #[arg(long, conflicts_with_all = ["tui", "follow"])]
+    json: bool,
+
+    /// Run a council for each directory this many levels down, then synthesize across them
+    #[arg(long, value_name = "DEPTH", conflicts_with_all = ["follow", "json", "no_synthesize"])]
+    per_dir: Option<usize>,
+
+    /// Directory councils to run at once with --per-dir
+    #[arg(long, default_value_t = 2, requires = "per_dir")]
+    jobs: usize,
+
+    /// Install council globally to ~/.cargo/bin
+    #[arg(long)]
+    install: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// List past runs, most recent first
+    History {
+        /// Only show runs whose task or synthesis contains this text
+        query: Option<String>,
+
+        /// Maximum number of runs to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Re-render a past run
+    Show {
+        /// Run id (see `council history`)
+        id: i64,
+
+        /// Show all individual analyses (default: synthesis only)
+        #[arg(long)]
+        all: bool,
+    },
+    /// Compare the findings of two past runs
+    Diff {
+        /// Earlier run id
+        a: i64,
+
+        /// Later run id
+        b: i64,
+    },
+    /// Export a past run's findings as inline review comments (JSON) for a code review API
+    Comments {
+        /// Run id (see `council history`)
+        id: i64,
+
+        /// Include every member's findings, not just the synthesis's
+        #[arg(long)]
+        members: bool,
+
+        /// Write the JSON to this file instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Re-run only the synthesis of a past run, saved as a new run
+    Synthesize {
+        /// Run id (see `council history`)
+        id: i64,
+
+        /// Model to synthesize with (default: the run's model)
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Synthesis template: a built-in name (default, adr, review, verdict) or a file path
+        #[arg(short, long, value_name = "NAME|PATH")]
+        template: Option<String>,
+
+        /// Extra guidance for the synthesizer, e.g. "focus on security"
+        #[arg(short, long)]
+        instructions: Option<String>,
+
+        /// Only synthesize these members (numbers or constraint names, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        members: Vec<String>,
+
+        /// Show the analyses being synthesized
+        #[arg(long)]
+        all: bool,
+
+        /// Save a Markdown report of the new run
+        #[arg(long, value_name = "PATH")]
+        report: Option<PathBuf>,
+    },
+    /// Re-run the members of a past run that did not succeed, then synthesize again
+    Resume {
+        /// Run id (see `council history`)
+        id: i64,
+
+        /// Show all individual analyses (default: synthesis only)
+        #[arg(long)]
+        all: bool,
+
+        /// Stop once resumed members have cost this many US dollars
+        #[arg(long, value_name = "USD")]
+        max_cost: Option<f64>,
+
+        /// Stop once resumed members have used this many tokens
+        #[arg(long, value_name = "TOKENS")]
+        max_tokens: Option<u64>,
+
+        /// Save a Markdown report of the completed run
+        #[arg(long, value_name = "PATH")]
+        report: Option<PathBuf>,
+    },
+    /// Run a council for every task in a YAML file, and write an index of their reports
+    Batch {
+        /// Batch file: a list of tasks, each a string or a mapping with `task` and settings
+        file: PathBuf,
+
+        /// Councils to run at once
+        #[arg(long, default_value_t = 2)]
+        jobs: usize,
+
+        /// Directory for each run's report and the index (default: council-batch)
+        #[arg(short, long, value_name = "DIR", default_value = "council-batch")]
+        out: PathBuf,
+
+        /// Apply a named profile to entries that do not name their own
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+    },
+    /// Re-run a council whenever files under the given paths change
+    Watch {
+        /// Files or directories to watch
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Task for the council, after `--`
+        #[arg(last = true, required = true, value_name = "TASK")]
+        task: Vec<String>,
+
+        /// Profile the council runs with
+        #[arg(long, value_name = "NAME", default_value = "quick")]
+        profile: String,
+
+        /// Wait until the files have been unchanged this long before re-running
+        #[arg(long, value_name = "MS", default_value_t = 1000)]
+        debounce: u64,
+
+        /// Re-run only the lenses whose previous findings cite a changed file
+        #[arg(long)]
+        relevant: bool,
+
+        /// Show all individual analyses (default: synthesis only)
+        #[arg(long)]
+        all: bool,
+    },
+    /// Have a quick council review changes from a git hook before they are committed or pushed
+    Hook {
+        #[command(subcommand)]
+        action: HookCommand,
+    },
+    /// Serve councils to other programs
+    Serve {
+        /// Speak the Model Context Protocol over stdin/stdout, for editors and agents
+        #[arg(long, conflicts_with = "http", required_unless_present = "http")]
+        mcp: bool,
+
+        /// Serve the HTTP job API on this address, e.g. 127.0.0.1:8080
+        #[arg(long, value_name = "ADDR")]
+        http: Option<String>,
+
+        /// Councils the HTTP server runs at once
+        #[arg(long, default_value_t = 2, requires = "http")]
+        jobs: usize,
+
+        /// Submitted councils that may wait for a free slot before new ones are refused
+        #[arg(long, default_value_t = 16, requires = "http")]
+        queue: usize,
+    },
+    /// Inspect the layered configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Print the effective settings for a new run and where each one came from
+    Show {
+        /// Resolve as if this profile were selected
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum HookCommand {
+    /// Install the hook in the current repository
+    Install {
+        /// Git hook to install
+        #[arg(long, value_enum, default_value_t = HookKind::PrePush)]
+        hook: HookKind,
+
+        /// Profile the review runs with
+        #[arg(long, value_name = "NAME", default_value = "quick")]
+        profile: String,
+
+        /// Findings of the synthesis that stop the commit or push
+        #[arg(long, value_enum, default_value_t = BlockPolicy::P0)]
+        block: BlockPolicy,
+
+        /// Replace an existing hook council did not install (it is restored on uninstall)
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove the hook council installed, restoring any hook it replaced
+    Uninstall {
+        /// Git hook to remove
+        #[arg(long, value_enum, default_value_t = HookKind::PrePush)]
+        hook: HookKind,
+    },
+    /// Review the changes as the installed hook does (run by the hook itself)
+    #[command(hide = true)]
+    Run {
+        #[arg(long, value_enum)]
+        hook: HookKind,
+
+        #[arg(long, value_name = "NAME", default_value = "quick")]
+        profile: String,
+
+        #[arg(long, value_enum, default_value_t = BlockPolicy::P0)]
+        block: BlockPolicy,
+    },
+}
+
+fn read_task(task: Option<String>, task_file: Option<&std::path::Path>) -> Result<Option<String>, String> {
+    let text = match (task, task_file) {
+        (_, Some(path)) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read task file {}: {}", path.display(), e))?,
+        (Some(t), None) if t == "-" => {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buf)
+                .map_err(|e| format!("Cannot read task from stdin: {}", e))?;
+            buf
+        }
+        (Some(t), None) => t,
+        (None, None) => return Ok(None),
+    };
+
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Task is empty".to_string());
+    }
+    Ok(Some(text.to_string()))
+}
+
+fn install_globally() -> Result<(), Box<dyn std::error::Error>> {
+    let exe_path = std::env::current_exe()?;
+
+    // Install to ~/.cargo/bin (where cargo install puts things)
+    let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"))?;
+    let cargo_bin = std::path::PathBuf::from(home).join(".cargo").join("bin");
+
+    // Create ~/.cargo/bin if it doesn't exist
+    std::fs::create_dir_all(&cargo_bin)?;
+
+    let install_path = cargo_bin.join("council");
+
+    println!("{}", "Installing council...".green());
+    println!("  Source: {}", exe_path.display());
+    println!("  Target: {}", install_path.display());
+
+    // Copy the file
+    std::fs::copy(&exe_path, &install_path)?;
+
+    // Make it executable (Unix only)
+    #[cfg(unix)]
+    {
+        std::fs::set_permissions(&install_path, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    println!();
+    println!("{}", "Successfully installed!".green().bold());
+    println!();
+    println!("Run: {} \"your task\"", "council".cyan());
+    println!();
+    println!("{}", "Note:".yellow());
+    println!("  Make sure ~/.cargo/bin is in your PATH");
+    println!("  If 'council' is not found, add this to your ~/.bashrc or ~/.zshrc:");
+    println!("    export PATH=\"$HOME/.cargo/bin:$PATH\"");
+
+    Ok(())
+}
+
+/// Options for `council synthesize`.
+struct SynthesizeOptions {
+    model: Option<String>,
+    template: Option<PromptTemplate>,
+    instructions: Option<String>,
+    members: Vec<String>,
+    all: bool,
+}
+
+/// Apply explicit CLI flags, the top layer of the config.
+/// The setting a `--x`/`--no-x` flag pair asks for, if either was given; the last one wins.
+fn flag_pair(on: bool, off: bool) -> Option<bool> {
+    match (on, off) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+fn apply_args(config: &mut Config, args: &Args) {
+    let mut flagged = Vec::new();
+    if let Some(n) = args.num {
+        config.num = n;
+        flagged.push("num");
+    }
+    if let Some(t) = args.timeout {
+        config.timeout = t;
+        flagged.push("timeout");
+    }
+    if let Some(m) = &args.model {
+        config.model = Some(m.clone());
+        flagged.push("model");
+    }
+    if let Some(p) = args.permissions {
+        config.permissions = p;
+        flagged.push("permissions");
+    }
+    if let Some(p) = args.synthesis_permissions {
+        config.synthesis_permissions = p;
+        flagged.push("synthesis_permissions");
+    }
+    if let Some(i) = args.isolate {
+        config.isolate = i;
+        flagged.push("isolate");
+    }
+    if let Some(synthesize) = flag_pair(args.synthesize, args.no_synthesize) {
+        config.synthesize = synthesize;
+        flagged.push("synthesize");
+    }
+    if let Some(all) = flag_pair(args.all, args.no_all) {
+        config.all = all;
+        flagged.push("all");
+    }
+    if let Some(tui) = flag_pair(args.tui, args.no_tui) {
+        config.tui = tui;
+        flagged.push("tui");
+    }
+    if let Some(t) = &args.synthesis_template {
+        config.synthesis_template = Some(t.clone());
+        flagged.push("synthesis_template");
+    }
+    if !args.member_template.is_empty() {
+        config.member_template = args.member_template.clone();
+        flagged.push("member_template");
+    }
+    if !args.file.is_empty() {
+        config.file = args.file.clone();
+        flagged.push("file");
+    }
+    if args.max_cost.is_some() {
+        config.max_cost = args.max_cost;
+        flagged.push("max_cost");
+    }
+    if args.max_tokens.is_some() {
+        config.max_tokens = args.max_tokens;
+        flagged.push("max_tokens");
+    }
+    if let Some(r) = &args.report {
+        config.report = Some(r.clone());
+        flagged.push("report");
+    }
+    match flag_pair(args.cache, args.no_cache) {
+        Some(true) => {
+            config.cache = true;
+            flagged.push("cache");
+        }
+        Some(false) => {
+            config.cache = false;
+            config.refresh = false;
+            flagged.extend(["cache", "refresh"]);
+        }
+        None => {}
+    }
+    if args.refresh {
+        config.refresh = true;
+        flagged.push("refresh");
+    }
+    if args.seed.is_some() {
+        config.seed = args.seed;
+        flagged.push("seed");
+    }
+    if let Some(history) = flag_pair(args.history, args.no_history) {
+        config.history = history;
+        flagged.push("history");
+    }
+    for key in flagged {
+        config.sources.insert(key, "command line".to_string());
+    }
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{} {}", "Error:".red().bold(), message);
+    std::process::exit(1);
+}
+
+async fn run_subcommand(command: Commands) {
+    // Only the commands that read saved runs need the database
+    let open_history = || History::open().unwrap_or_else(|e| fail(e));
+    match command {
+        Commands::History { query, limit } => {
+            let history = open_history();
+            let runs = history.list(query.as_deref(), limit).unwrap_or_else(|e| fail(e));
+            print_history(&runs);
+        }
+        Commands::Show { id, all } => {
+            let history = open_history();
+            let run = history.load(id).unwrap_or_else(|e| fail(e));
+            print_run(&run, all);
+        }
+        Commands::Diff { a, b } => {
+            let history = open_history();
+            let run_a = history.load(a).unwrap_or_else(|e| fail(e));
+            let run_b = history.load(b).unwrap_or_else(|e| fail(e));
+            print_run_diff(&run_a, &run_b);
+        }
+        Commands::Comments { id, members, out } => {
+            let history = open_history();
+            let run = history.load(id).unwrap_or_else(|e| fail(e));
+            let json = serde_json::to_string_pretty(&review_comments_json(&run, members)).unwrap_or_default();
+            match out {
+                Some(path) => std::fs::write(&path, json + "\n")
+                    .unwrap_or_else(|e| fail(format!("Cannot write {}: {}", path.display(), e))),
+                None => println!("{}", json),
+            }
+        }
+        Commands::Synthesize { id, model, template, instructions, members, all, report } => {
+            let history = open_history();
+            let mut source = history.load(id).unwrap_or_else(|e| fail(e));
+            source.settings.backend = Config::resolve(None).unwrap_or_else(|e| fail(e)).backend;
+            let template = template.map(|t| load_synthesis_template(&t).unwrap_or_else(|e| fail(e)));
+            ensure_claude_installed(&source.settings.backend);
+            let options = SynthesizeOptions { model, template, instructions, members, all };
+            let run = resynthesize_run(&source, options, history).await;
+            write_report(&run, report.as_deref());
+            if let Some(id) = run.id {
+                println!("\n  {}: council show {}", "Saved as run".cyan(), id);
+            }
+        }
+        Commands::Resume { id, all, max_cost, max_tokens, report } => {
+            let history = open_history();
+            let run = history.load(id).unwrap_or_else(|e| fail(e));
+            let run = resume_run(run, history, Budget::new(max_cost, max_tokens), all).await;
+            write_report(&run, report.as_deref());
+        }
+        Commands::Batch { file, jobs, out, profile } => {
+            run_batch(&file, jobs, &out, profile.as_deref()).await.unwrap_or_else(|e| fail(e));
+        }
+        Commands::Watch { paths, task, profile, debounce, relevant, all } => {
+            let config = Config::resolve(Some(&profile)).unwrap_or_else(|e| fail(e));
+            let task = read_task(Some(task.join(" ")), None).unwrap_or_else(|e| fail(e)).unwrap_or_default();
+            let options = WatchOptions { paths, task, debounce: Duration::from_millis(debounce), relevant, all };
+            watch(&config, options).await.unwrap_or_else(|e| fail(e));
+        }
+        Commands::Hook { action: HookCommand::Install { hook, profile, block, force } } => {
+            hook::install(hook, &profile, block, force).unwrap_or_else(|e| fail(e));
+        }
+        Commands::Hook { action: HookCommand::Uninstall { hook } } => {
+            hook::uninstall(hook).unwrap_or_else(|e| fail(e));
+        }
+        Commands::Hook { action: HookCommand::Run { hook, profile, block } } => {
+            hook::run(hook, &profile, block).await.unwrap_or_else(|e| fail(e));
+        }
+        Commands::Serve { http: Some(addr), jobs, queue, .. } => {
+            serve_http(&addr, jobs, queue).await.unwrap_or_else(|e| fail(e));
+        }
+        Commands::Serve { .. } => serve_mcp().await,
+        Commands::Config { action: ConfigCommand::Show { profile } } => {
+            let config = Config::resolve(profile.as_deref()).unwrap_or_else(|e| fail(e));
+            print_config(&config);
+        }
+    }
+}
+
+/// Re-run every member that did not succeed, then synthesize over the complete set.
+async fn resume_run(mut run: Run, history: History, budget: Budget, all: bool) -> Run {
+    if let Err(e) = std::env::set_current_dir(&run.workdir) {
+        fail(format!("Cannot enter run directory {}: {}", run.workdir.display(), e));
+    }
+    // The backend is not stored with the run; use the one configured for its directory
+    run.settings.backend = Config::resolve(None).unwrap_or_else(|e| fail(e)).backend;
+    ensure_claude_installed(&run.settings.backend);
+
+    let mut retry = 0;
+    for m in run.members.iter_mut().filter(|m| m.status != MemberStatus::Success) {
+        m.status = MemberStatus::Pending;
+        m.text.clear();
+        m.usage = Usage::default();
+        m.elapsed = Duration::ZERO;
+        m.cached = false;
+        retry += 1;
+    }
+
+    let synthesis_ok = run.synthesis.as_ref().map(|s| s.text.is_ok()).unwrap_or(false);
+    if retry == 0 && (synthesis_ok || !run.settings.synthesize) {
+        println!("Run #{} already completed; nothing to resume.", run.id.unwrap_or_default());
+        return run;
+    }
+
+    run.budget_note = None;
+    run.synthesis = None;
+
+    println!(
+        "{} run #{}: {} of {} members",
+        "Resuming".cyan(),
+        run.id.unwrap_or_default(),
+        retry,
+        run.members.len()
+    );
+    let mut council = Council::new(run);
+    council.history = Some(history);
+    council.budget = budget;
+    watch_council(council, &ViewOptions { all, ..ViewOptions::default() }).await.unwrap_or_else(|e| fail(e))
+}
+
+fn ensure_claude_installed(backend: &Backend) {
+    // Validate Claude CLI exists before spawning N processes
+    if !backend_installed(backend) {
+        eprintln!("{}", format!("Error: '{}' CLI not found in PATH", backend.command).red().bold());
+        eprintln!();
+        eprintln!("Please install Claude Code first:");
+        eprintln!("  https://docs.anthropic.com/claude/docs/claude-code");
+        std::process::exit(1);
+    }
+}
+
+fn write_report(run: &Run, path: Option<&Path>) {
+    if let Some(path) = path {
+        match std::fs::write(path, render_report(run)) {
+            Ok(()) => eprintln!("\n  {}: {}", "Report saved".cyan(), path.display()),
+            Err(e) => eprintln!("{} Cannot write report {}: {}", "Error:".red().bold(), path.display(), e),
+        }
+    }
+}
+
+/// Build a new run from `source`'s stored member outputs and synthesize only that.
+async fn resynthesize_run(source: &Run, options: SynthesizeOptions, history: History) -> Run {
+    let selected: Vec<MemberOutput> = source
+        .finished_members()
+        .into_iter()
+        .filter(|m| {
+            options.members.is_empty()
+                || options.members.iter().any(|sel| *sel == (m.id + 1).to_string() || sel.eq_ignore_ascii_case(&m.name))
+        })
+        .map(|m| MemberOutput { usage: Usage::default(), cached: false, ..m.clone() })
+        .collect();
+    for sel in &options.members {
+        if !selected.iter().any(|m| *sel == (m.id + 1).to_string() || sel.eq_ignore_ascii_case(&m.name)) {
+            fail(format!("Run #{} has no finished member '{}'", source.id.unwrap_or_default(), sel));
+        }
+    }
+    if selected.is_empty() {
+        fail(format!("Run #{} has no finished members to synthesize", source.id.unwrap_or_default()));
+    }
+
+    let mut run = Run {
+        id: None,
+        parent_id: source.id,
+        created_at: unix_now(),
+        task: source.task.clone(),
+        seed: source.seed,
+        workdir: source.workdir.clone(),
+        settings: RunSettings {
+            synthesize: true,
+            synthesis_model: options.model.or_else(|| source.settings.synthesis_model.clone()),
+            synthesis_instructions: options.instructions,
+            synthesis_template: options.template.or_else(|| source.settings.synthesis_template.clone()),
+            ..source.settings.clone()
+        },
+        members: selected,
+        synthesis: None,
+        member_elapsed: Duration::ZERO,
+        total_elapsed: Duration::ZERO,
+        budget_note: None,
+    };
+
+    match history.insert_run(&run) {
+        Ok(id) => run.id = Some(id),
+        Err(e) => eprintln!("{} {} (run will not be saved)", "Warning:".yellow().bold(), e),
+    }
+
+    // Every member has already succeeded, so the council goes straight to synthesis
+    let mut council = Council::new(run);
+    council.history = Some(history);
+    watch_council(council, &ViewOptions { all: options.all, ..ViewOptions::default() })
+        .await
+        .unwrap_or_else(|e| fail(e))
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    if let Some(command) = args.command {
+        run_subcommand(command).await;
+        return;
+    }
+
+    // Handle install flag
+    if args.install {
+        match install_globally() {
+            Ok(_) => std::process::exit(0),
+            Err(e) => fail(e),
+        }
+    }
+
+    let mut config = Config::resolve(args.profile.as_deref()).unwrap_or_else(|e| fail(e));
+    apply_args(&mut config, &args);
+
+    // Ensure task was provided
+    let task = match read_task(args.task, args.task_file.as_deref()) {
+        Ok(Some(t)) => t,
+        Ok(None) => {
+            eprintln!("{}", "Error: <TASK> argument is required".red().bold());
+            eprintln!();
+            eprintln!("Usage: council [OPTIONS] <TASK>");
+            eprintln!("       council [OPTIONS] --task-file <PATH>");
+            eprintln!("       <command> | council [OPTIONS] -");
+            eprintln!("       council history [QUERY]");
+            eprintln!("       council show <ID>");
+            eprintln!("       council resume <ID>");
+            eprintln!("       council batch <FILE>");
+            eprintln!("       council watch <PATHS>... -- <TASK>");
+            eprintln!("       council hook install");
+            eprintln!("       council config show");
+            eprintln!("       council serve --mcp");
+            eprintln!("       council --install");
+            eprintln!();
+            eprintln!("For more information try '--help'");
+            std::process::exit(1);
+        }
+        Err(e) => fail(e),
+    };
+
+    ensure_claude_installed(&config.backend);
+
+    if config.tui && !args.json && !std::io::stdout().is_terminal() {
+        eprintln!("{} --tui needs a terminal (showing plain output)", "Warning:".yellow().bold());
+        config.tui = false;
+    }
+
+    // --per-dir builds its runs itself, and --follow conflicts with it
+    let mut follow = None;
+    let council = match args.per_dir {
+        Some(_) if !config.synthesize => fail("--per-dir needs synthesis (synthesize = false is set)"),
+        Some(depth) => fan_out(&config, &task, depth, args.jobs).await.unwrap_or_else(|e| fail(e)),
+        None => {
+            let run = new_run(&config, task).unwrap_or_else(|e| fail(e));
+            follow = args.follow.as_deref().map(|sel| {
+                run.members
+                    .iter()
+                    .find(|m| *sel == (m.id + 1).to_string() || sel.eq_ignore_ascii_case(&m.name))
+                    .map(|m| m.id)
+                    .unwrap_or_else(|| {
+                        let names: Vec<String> =
+                            run.members.iter().map(|m| format!("#{} {}", m.id + 1, m.name)).collect();
+                        fail(format!("--follow: no member '{}' in this run ({})", sel, names.join(", ")))
+                    })
+            });
+            Council::from_config(&config, run)
+        }
+    };
+
+    let view = ViewOptions { all: config.all, follow, tui: config.tui, json: args.json, ..ViewOptions::default() };
+    let run = watch_council(council, &view).await.unwrap_or_else(|e| fail(e));
+
+    write_report(&run, config.report.as_deref());
+
+    // The JSON stream on stdout ends with `run_finished`, which carries the run id
+    if let (Some(id), false) = (run.id, args.json) {
+        println!("\n  {}: council show {}", "Saved as run".cyan(), id);
+        if run.members.iter().any(|m| m.status != MemberStatus::Success) {
+            println!("  {}: council resume {}", "Retry unfinished members".cyan(), id);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn applied(config: &Config, flags: &[&str]) -> Config {
+        let args = Args::try_parse_from(std::iter::once("council").chain(flags.iter().copied()).chain(["task"])).unwrap();
+        let mut config = config.clone();
+        apply_args(&mut config, &args);
+        config
+    }
+
+    #[test]
+    fn args_are_consistent() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn flags_turn_config_booleans_either_way() {
+        let mut on = Config::defaults();
+        (on.all, on.tui, on.cache, on.refresh) = (true, true, true, true);
+        let off = Config { synthesize: false, history: false, ..Config::defaults() };
+
+        let config = applied(&on, &["--no-all", "--no-tui", "--no-cache"]);
+        assert!(!config.all && !config.tui && !config.cache && !config.refresh);
+        assert_eq!(config.sources.get("all").map(String::as_str), Some("command line"));
+
+        let config = applied(&off, &["--synthesize", "--history"]);
+        assert!(config.synthesize && config.history);
+    }
+
+    #[test]
+    fn last_of_a_flag_pair_wins() {
+        let config = applied(&Config::defaults(), &["--all", "--no-all", "--no-synthesize", "--synthesize"]);
+        assert!(!config.all && config.synthesize);
+
+        // Flags left out keep the config's value
+        let config = applied(&Config { all: true, ..Config::defaults() }, &[]);
+        assert!(config.all);
+        assert!(!config.sources.contains_key("all"));
+    }
+}
@@ -0,0 +1,191 @@
+//! MCP server (`council serve --mcp`: JSON-RPC 2.0 over stdin/stdout).
+
+use crate::frontend::{watch_council, ViewOptions};
+use council::prompt::SYNTHESIS_TEMPLATES;
+use council::{backend_installed, new_run, render_report, Config, Council, History, CONSTRAINTS};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+/// Protocol revisions this server can speak; the first is offered when the client asks for another.
+const MCP_PROTOCOL_VERSIONS: [&str; 3] = ["2024-11-05", "2025-03-26", "2025-06-18"];
+
+fn mcp_tools() -> serde_json::Value {
+    serde_json::json!([
+        {
+            "name": "run_council",
+            "description": "Run a council: several Claude instances analyze the task through different expert \
+                            lenses in the server's working directory, then a synthesis consolidates them. \
+                            Returns the run id and a Markdown report. Takes minutes.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "task": { "type": "string", "description": "What the council should analyze" },
+                    "members": { "type": "integer", "minimum": 1, "description": "Number of council members" },
+                    "model": { "type": "string", "description": "Model for members and synthesis (sonnet, opus, haiku)" },
+                    "profile": { "type": "string", "description": "Config profile to apply, e.g. quick or deep" },
+                    "synthesis_template": { "type": "string", "description": "default, adr, review or verdict" }
+                },
+                "required": ["task"]
+            }
+        },
+        {
+            "name": "list_constraints",
+            "description": "List the expert lenses council members analyze through; mandatory ones are in every run.",
+            "inputSchema": { "type": "object", "properties": {} }
+        },
+        {
+            "name": "get_run",
+            "description": "Fetch a saved run as a Markdown report: synthesis, every member's analysis and costs.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "id": { "type": "integer", "description": "Run id" } },
+                "required": ["id"]
+            }
+        }
+    ])
+}
+
+/// Serve MCP requests until stdin closes. Requests are handled concurrently, so a long
+/// `run_council` does not block other calls; runs hold the history database, which is not
+/// `Sync`, so they stay on this thread.
+pub async fn serve_mcp() {
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<serde_json::Value>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(message) = out_rx.recv().await {
+            let line = format!("{}\n", message);
+            if stdout.write_all(line.as_bytes()).await.is_err() || stdout.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let local = tokio::task::LocalSet::new();
+    local
+        .run_until(async {
+            let mut lines = BufReader::new(tokio::io::stdin()).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let out = out_tx.clone();
+                match serde_json::from_str::<serde_json::Value>(&line) {
+                    Ok(request) => {
+                        tokio::task::spawn_local(async move {
+                            if let Some(response) = handle_mcp_request(request).await {
+                                let _ = out.send(response);
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        let _ = out.send(serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "id": null,
+                            "error": { "code": -32700, "message": format!("Parse error: {}", e) },
+                        }));
+                    }
+                }
+            }
+        })
+        .await;
+    // Let runs still in flight finish and answer
+    local.await;
+    drop(out_tx);
+    let _ = writer.await;
+}
+
+/// Answer one JSON-RPC message; notifications get no response.
+async fn handle_mcp_request(request: serde_json::Value) -> Option<serde_json::Value> {
+    let id = request.get("id").cloned();
+    let method = request["method"].as_str().unwrap_or_default();
+    let result = match method {
+        "initialize" => Ok(serde_json::json!({
+            "protocolVersion": request["params"]["protocolVersion"]
+                .as_str()
+                .filter(|v| MCP_PROTOCOL_VERSIONS.contains(v))
+                .unwrap_or(MCP_PROTOCOL_VERSIONS[0]),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "council", "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "ping" => Ok(serde_json::json!({})),
+        "tools/list" => Ok(serde_json::json!({ "tools": mcp_tools() })),
+        "tools/call" => Ok(call_mcp_tool(&request["params"]).await),
+        _ => Err((-32601, format!("Method not found: {}", method))),
+    };
+    let id = id?;
+    Some(match result {
+        Ok(result) => serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => {
+            serde_json::json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+        }
+    })
+}
+
+/// Run a tool; failures are reported in the result (`isError`) so the calling model sees them.
+async fn call_mcp_tool(params: &serde_json::Value) -> serde_json::Value {
+    let args = &params["arguments"];
+    let outcome = match params["name"].as_str().unwrap_or_default() {
+        "run_council" => mcp_run_council(args).await,
+        "list_constraints" => Ok(list_constraints_json()),
+        "get_run" => match args["id"].as_i64() {
+            Some(id) => History::open().and_then(|h| h.load(id)).map(|run| format!("Run #{}\n\n{}", id, render_report(&run))),
+            None => Err("get_run needs an integer id".to_string()),
+        },
+        other => Err(format!("Unknown tool: {}", other)),
+    };
+    match outcome {
+        Ok(text) => serde_json::json!({ "content": [{ "type": "text", "text": text }] }),
+        Err(e) => serde_json::json!({ "content": [{ "type": "text", "text": e }], "isError": true }),
+    }
+}
+
+/// Settings for a run requested over MCP or HTTP: the config files, then the request's
+/// `profile`, `members`, `model` and `synthesis_template`. Requests may only name built-in
+/// templates: a path would let clients read any file the server can and get it back in the report.
+pub fn council_config(request: &serde_json::Value) -> Result<Config, String> {
+    let mut config = Config::resolve(request["profile"].as_str())?;
+    if let Some(n) = request["members"].as_u64() {
+        config.num = n as usize;
+    }
+    if let Some(model) = request["model"].as_str() {
+        config.model = Some(model.to_string());
+    }
+    if let Some(template) = request["synthesis_template"].as_str() {
+        if !SYNTHESIS_TEMPLATES.iter().any(|(name, _)| *name == template) {
+            let names: Vec<&str> = SYNTHESIS_TEMPLATES.iter().map(|(name, _)| *name).collect();
+            return Err(format!("Unknown synthesis template '{}' (built-in templates: {})", template, names.join(", ")));
+        }
+        config.synthesis_template = Some(template.to_string());
+    }
+    Ok(config)
+}
+
+async fn mcp_run_council(args: &serde_json::Value) -> Result<String, String> {
+    let task = args["task"]
+        .as_str()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .ok_or("run_council needs a non-empty task")?;
+    let config = council_config(args)?;
+    if !backend_installed(&config.backend) {
+        return Err(format!("'{}' CLI not found in PATH", config.backend.command));
+    }
+
+    let run = new_run(&config, task.to_string())?;
+    let council = Council::from_config(&config, run);
+    let run = watch_council(council, &ViewOptions { quiet: true, ..ViewOptions::default() }).await?;
+    let heading = match run.id {
+        Some(id) => format!("Run #{}", id),
+        None => "Run (not saved)".to_string(),
+    };
+    Ok(format!("{}\n\n{}", heading, render_report(&run)))
+}
+
+fn list_constraints_json() -> String {
+    let constraints: Vec<serde_json::Value> = CONSTRAINTS
+        .iter()
+        .map(|c| serde_json::json!({ "name": c.name, "mandatory": c.mandatory, "prompt": c.prompt }))
+        .collect();
+    serde_json::to_string_pretty(&constraints).unwrap_or_default()
+}
+
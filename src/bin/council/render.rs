@@ -0,0 +1,595 @@
+//! Terminal output: run headers, member status, live status lines, reports and diffs.
+
+use colored::*;
+use council::config::CONFIG_KEYS;
+use council::findings::{diff_findings, diff_lines, extract_findings, Finding, Priority};
+use council::history::RunSummary;
+use council::report::{cost_rows, format_tokens};
+use council::{
+    verify_citations, Config, Isolation, MemberOutput, MemberStatus, Run, SourceCitations, StreamProgress,
+    SynthesisOutput, CONSTRAINTS,
+};
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Shorten text to at most `max` characters for single-line display.
+pub fn truncate_chars(text: &str, max: usize) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}...", &flat[..idx]),
+        None => flat,
+    }
+}
+
+pub fn print_cost_table(run: &Run) {
+    let rows = cost_rows(run);
+    let width = rows.iter().map(|r| r[0].len()).max().unwrap_or(0).max(6);
+
+    println!();
+    println!(
+        "  {:<width$}  {:>8}  {:>9}  {:>9}  {:>10}",
+        "MEMBER".cyan(),
+        "TIME".cyan(),
+        "INPUT".cyan(),
+        "OUTPUT".cyan(),
+        "COST".cyan(),
+        width = width
+    );
+    for (i, r) in rows.iter().enumerate() {
+        let line = format!(
+            "  {:<width$}  {:>8}  {:>9}  {:>9}  {:>10}",
+            r[0], r[1], r[2], r[3], r[4],
+            width = width
+        );
+        if i == rows.len() - 1 {
+            println!("{}", line.bold());
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+pub fn print_config(config: &Config) {
+    println!("{}", "Config files:".cyan());
+    if config.files.is_empty() {
+        println!("  {}", "(none)".dimmed());
+    }
+    for path in &config.files {
+        println!("  {}", path.display());
+    }
+    println!();
+    for warning in &config.warnings {
+        println!("{} {}", "Warning:".yellow().bold(), warning);
+    }
+    if !config.warnings.is_empty() {
+        println!();
+    }
+    match &config.profile {
+        Some((name, source)) => println!("{} {} {}", "Profile:".cyan(), name, format!("({})", source).dimmed()),
+        None => println!("{} {}", "Profile:".cyan(), "(none)".dimmed()),
+    }
+    println!();
+
+    let width = CONFIG_KEYS.iter().map(|k| k.len()).max().unwrap_or(0);
+    let values: Vec<String> = CONFIG_KEYS.iter().map(|k| config.display_value(k)).collect();
+    let value_width = values.iter().map(|v| v.chars().count()).max().unwrap_or(0).min(40);
+    for (key, value) in CONFIG_KEYS.iter().zip(values) {
+        let source = config.sources.get(key).map(String::as_str).unwrap_or("default");
+        println!(
+            "  {:<width$}  {:<value_width$}  {}",
+            key,
+            value,
+            source.dimmed(),
+            width = width,
+            value_width = value_width
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Output rendering (shared by live runs and `council show`)
+// ---------------------------------------------------------------------------
+
+pub fn print_header(run: &Run) {
+    let settings = &run.settings;
+
+    println!();
+    println!("{}", "=".repeat(60).green());
+    println!("{}", "                 COUNCIL OF CLAUDES".green().bold());
+    println!("{}", "=".repeat(60).green());
+    println!();
+    if let Some(id) = run.id {
+        println!("  {}: #{}", "Run".cyan(), id);
+    }
+    if let Some(parent) = run.parent_id {
+        println!("  {}: #{}", "Re-synthesis of".cyan(), parent);
+    }
+    println!("  {}: {}", "Members".cyan(), run.members.len());
+    println!("  {}: {}s per member", "Timeout".cyan(), settings.timeout);
+    if let Some(ref m) = settings.model {
+        println!("  {}: {}", "Model".cyan(), m);
+    }
+    if let Some(ref m) = settings.synthesis_model {
+        println!("  {}: {}", "Synthesis model".cyan(), m);
+    }
+    if let Some(ref t) = settings.synthesis_template {
+        println!("  {}: {}", "Synthesis template".cyan(), t.name);
+    }
+    if let Some(ref i) = settings.synthesis_instructions {
+        println!("  {}: {}", "Synthesis instructions".cyan(), truncate_chars(i, 50));
+    }
+    println!("  {}: {}", "Permissions".cyan(), settings.permissions.as_str());
+    if settings.isolation != Isolation::None {
+        println!("  {}: {}", "Isolation".cyan(), settings.isolation.as_str());
+    }
+    println!("  {}: {}", "Synthesize".cyan(), if settings.synthesize { "yes" } else { "no" });
+    println!("  {}: {}", "Seed".cyan(), run.seed);
+    println!("  {}: {}", "Task".cyan(), truncate_chars(&run.task, 50));
+    println!();
+
+    // Show constraint assignments
+    for m in &run.members {
+        let mandatory = CONSTRAINTS.iter().any(|c| c.name == m.name && c.mandatory);
+        let marker = if mandatory {
+            " (mandatory)".yellow()
+        } else {
+            "".normal()
+        };
+        let cached = if m.cached { " (cached)".cyan() } else { "".normal() };
+        println!("  Member #{}: {}{}{}", m.id + 1, m.name.to_uppercase().blue(), marker, cached);
+    }
+
+    println!();
+    println!("{}", "=".repeat(60).green());
+    println!();
+}
+
+pub fn print_member_status(m: &MemberOutput) {
+    let label = match m.status {
+        MemberStatus::Pending => "[Spawning]".yellow(),
+        MemberStatus::Success if m.cached => "[Cached]".cyan(),
+        MemberStatus::Success => "[Completed]".green(),
+        MemberStatus::Failed => "[Failed]".red(),
+        MemberStatus::TimedOut => "[Timed out]".red(),
+        MemberStatus::Cancelled => "[Cancelled]".red(),
+    };
+    println!("{} Member #{}: {}", label, m.id + 1, m.name.to_uppercase().blue());
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1_000_000 {
+        format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+    } else if bytes >= 1_000 {
+        format!("{:.1} KB", bytes as f64 / 1_000.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// One running member's latest activity.
+struct LiveMember {
+    id: usize,
+    name: String,
+    started: Instant,
+    last_activity: Instant,
+    progress: StreamProgress,
+}
+
+/// Status lines for the running members. On a terminal they are redrawn in place below the
+/// scrolling output; otherwise a snapshot is printed every LIVE_SNAPSHOT_SECS.
+pub struct LiveBoard {
+    members: Vec<LiveMember>,
+    redraw: bool,
+    /// Lines currently drawn below the scrolling output
+    drawn: usize,
+    /// Member whose text is streamed as it arrives
+    follow: Option<usize>,
+    last_snapshot: Instant,
+}
+
+const LIVE_SNAPSHOT_SECS: u64 = 30;
+
+impl LiveBoard {
+    pub fn new(follow: Option<usize>) -> LiveBoard {
+        LiveBoard {
+            members: Vec::new(),
+            redraw: std::io::stdout().is_terminal(),
+            drawn: 0,
+            follow,
+            last_snapshot: Instant::now(),
+        }
+    }
+
+    pub fn start(&mut self, m: &MemberOutput) {
+        let now = Instant::now();
+        self.members.push(LiveMember {
+            id: m.id,
+            name: m.name.to_uppercase(),
+            started: now,
+            last_activity: now,
+            progress: StreamProgress::default(),
+        });
+        if self.follow == Some(m.id) {
+            println!("{} Member #{}: {}", "[Following]".cyan(), m.id + 1, m.name.to_uppercase().blue());
+        }
+    }
+
+    pub fn progress(&mut self, id: usize, progress: &StreamProgress) {
+        if let (Some(text), true) = (&progress.text, self.follow == Some(id)) {
+            self.clear();
+            println!("{}", text.trim_end());
+            self.draw();
+        }
+        if let Some(m) = self.members.iter_mut().find(|m| m.id == id) {
+            m.last_activity = Instant::now();
+            m.progress = progress.clone();
+        }
+    }
+
+    /// Print a line above the status lines.
+    pub fn note(&mut self, line: &str) {
+        self.clear();
+        println!("{}", line);
+        self.draw();
+    }
+
+    pub fn finish(&mut self, id: usize) {
+        self.members.retain(|m| m.id != id);
+    }
+
+    fn line(m: &LiveMember) -> String {
+        format!(
+            "  #{:<2} {:<23} {:>8} {:>11}  {:<10} {}",
+            m.id + 1,
+            m.name,
+            format_bytes(m.progress.bytes),
+            format!("{} tok", format_tokens(m.progress.output_tokens)),
+            m.progress.tool.as_deref().unwrap_or(""),
+            format!(
+                "idle {}s, {}s",
+                m.last_activity.elapsed().as_secs(),
+                m.started.elapsed().as_secs()
+            )
+            .dimmed()
+        )
+    }
+
+    /// Erase the status lines so scrolling output can be printed in their place.
+    pub fn clear(&mut self) {
+        if self.drawn > 0 {
+            print!("\x1b[{}A\x1b[J", self.drawn);
+            self.drawn = 0;
+        }
+    }
+
+    pub fn draw(&mut self) {
+        if !self.redraw {
+            return;
+        }
+        self.clear();
+        for m in &self.members {
+            println!("{}", LiveBoard::line(m));
+        }
+        self.drawn = self.members.len();
+        let _ = std::io::stdout().flush();
+    }
+
+    pub fn tick(&mut self) {
+        if self.redraw {
+            self.draw();
+        } else if self.last_snapshot.elapsed() >= Duration::from_secs(LIVE_SNAPSHOT_SECS) {
+            for m in &self.members {
+                println!("{}{}", "[Running]".yellow(), LiveBoard::line(m));
+            }
+            self.last_snapshot = Instant::now();
+        }
+    }
+}
+
+pub fn print_members_done(run: &Run) {
+    let finished = run.finished_members().len();
+    let num_members = run.members.len();
+
+    println!();
+    if let Some(reason) = &run.budget_note {
+        println!("{}", "=".repeat(60).red());
+        println!("{}", format!("     BUDGET EXCEEDED: {}", reason).red().bold());
+        println!(
+            "{}",
+            format!(
+                "     {} OF {} MEMBERS FINISHED, {} CANCELLED ({:.1}s)",
+                finished,
+                num_members,
+                num_members - finished,
+                run.member_elapsed.as_secs_f64()
+            )
+            .red()
+            .bold()
+        );
+        println!("{}", "=".repeat(60).red());
+    } else if finished < num_members {
+        println!("{}", "=".repeat(60).yellow());
+        println!(
+            "{}",
+            format!(
+                "     {} OF {} MEMBERS FINISHED ({:.1}s)",
+                finished,
+                num_members,
+                run.member_elapsed.as_secs_f64()
+            )
+            .yellow()
+            .bold()
+        );
+        println!("{}", "=".repeat(60).yellow());
+    } else {
+        println!("{}", "=".repeat(60).green());
+        println!(
+            "{}",
+            format!("     ALL {} MEMBERS COMPLETED ({:.1}s)", num_members, run.member_elapsed.as_secs_f64())
+                .green()
+                .bold()
+        );
+        println!("{}", "=".repeat(60).green());
+    }
+    println!();
+}
+
+pub fn print_analyses(run: &Run) {
+    for m in run.finished_members() {
+        println!();
+        println!("{}", "-".repeat(60).blue());
+        println!("  MEMBER #{}: {}", m.id + 1, m.name.to_uppercase().blue().bold());
+        println!("{}", "-".repeat(60).blue());
+        println!();
+        println!("{}", m.text);
+        println!();
+    }
+}
+
+pub fn print_synthesis_started() {
+    println!();
+    println!("{}", "=".repeat(60).magenta());
+    println!("{}", "              RUNNING SYNTHESIS...".magenta().bold());
+    println!("{}", "=".repeat(60).magenta());
+    println!();
+}
+
+pub fn print_synthesis(synthesis: &SynthesisOutput) {
+    println!();
+    println!("{}", "=".repeat(60).magenta());
+    println!("{}", "           SYNTHESIS & RECOMMENDATIONS".magenta().bold());
+    println!("{}", "=".repeat(60).magenta());
+    println!();
+
+    match &synthesis.text {
+        Ok(text) => println!("{}", text),
+        Err(e) => println!("{}", format!("[Synthesis failed: {}]", e).red()),
+    }
+}
+
+pub fn print_footer(run: &Run) {
+    if let Some(synthesis) = &run.synthesis {
+        println!();
+        println!("{}", "=".repeat(60).green());
+        println!(
+            "{}",
+            format!(
+                "        TOTAL TIME: {:.1}s (members: {:.1}s, synthesis: {:.1}s)",
+                run.total_elapsed.as_secs_f64(),
+                run.member_elapsed.as_secs_f64(),
+                synthesis.elapsed.as_secs_f64()
+            ).green().bold()
+        );
+        println!("{}", "=".repeat(60).green());
+    } else {
+        println!("{}", "=".repeat(60).green());
+        println!("{}", "                  END OF COUNCIL".green().bold());
+        println!("{}", "=".repeat(60).green());
+    }
+
+    print_cost_table(run);
+    print_citations(run);
+}
+
+/// How many of each analysis's `path:line` citations hold up in the working tree, and the ones
+/// that do not.
+pub fn print_citations(run: &Run) {
+    let checked = verify_citations(run);
+    if checked.iter().all(|s| s.citations.is_empty()) {
+        return;
+    }
+    let label = |s: &SourceCitations| match s.member {
+        Some(id) => format!("#{} {}", id + 1, s.name),
+        None => s.name.clone(),
+    };
+    let width = checked.iter().map(|s| label(s).len()).max().unwrap_or(0).max(9);
+
+    println!();
+    println!("  {:<width$}  {:>8}  {:>9}", "CITATIONS".cyan(), "VALID".cyan(), "ACCURACY".cyan(), width = width);
+    for s in checked.iter().filter(|s| !s.citations.is_empty()) {
+        let accuracy = s.accuracy().unwrap_or_default();
+        let line = format!(
+            "  {:<width$}  {:>8}  {:>8.0}%",
+            label(s),
+            format!("{}/{}", s.valid(), s.citations.len()),
+            accuracy * 100.0,
+            width = width
+        );
+        if accuracy < 1.0 {
+            println!("{}", line.yellow());
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    // Each invalid reference once, with every source that made it
+    let mut invalid: Vec<(String, &str, Vec<String>)> = Vec::new();
+    for s in &checked {
+        for c in &s.citations {
+            let Some(problem) = &c.problem else { continue };
+            let reference = c.location.to_string();
+            let source = match s.member {
+                Some(id) => format!("#{}", id + 1),
+                None => s.name.clone(),
+            };
+            match invalid.iter_mut().find(|(r, _, _)| *r == reference) {
+                Some((_, _, sources)) => sources.push(source),
+                None => invalid.push((reference, problem, vec![source])),
+            }
+        }
+    }
+    if !invalid.is_empty() {
+        println!();
+        println!("  {}", "Invalid citations:".yellow());
+        for (reference, problem, sources) in invalid {
+            println!("    {} {} {}", reference, format!("({})", problem).dimmed(), format!("[{}]", sources.join(", ")).dimmed());
+        }
+    }
+}
+
+/// Print a stored run exactly as it looked live.
+pub fn print_run(run: &Run, all: bool) {
+    print_header(run);
+    print_members_done(run);
+    if all {
+        print_analyses(run);
+    }
+    if let Some(synthesis) = &run.synthesis {
+        print_synthesis(synthesis);
+    }
+    print_footer(run);
+}
+
+pub fn print_history(runs: &[RunSummary]) {
+    if runs.is_empty() {
+        println!("No runs found.");
+        return;
+    }
+    for r in runs {
+        let status = format!("{}/{} ok", r.succeeded, r.members);
+        let status = if r.succeeded == r.members { status.green() } else { status.yellow() };
+        let task = match r.parent_id {
+            Some(parent) => format!("(re-synthesis of #{}) {}", parent, r.task),
+            None => r.task.clone(),
+        };
+        println!(
+            "  {}  {}  {:>9}  {:>9}  {}",
+            format!("#{:<5}", r.id).cyan(),
+            r.created_at,
+            status,
+            format!("${:.4}", r.cost_usd),
+            truncate_chars(&task, 60)
+        );
+    }
+}
+
+pub fn format_finding(f: &Finding) -> String {
+    match f.priority {
+        Some(p) if Priority::find(&f.text) != Some(p) => format!("[{}] {}", p.as_str(), f.text),
+        _ => f.text.clone(),
+    }
+}
+
+/// Print what changed between two versions of one analysis; returns (resolved, persisted, new).
+pub fn print_text_diff(old: &str, new: &str) -> (usize, usize, usize) {
+    let (old_findings, new_findings) = (extract_findings(old), extract_findings(new));
+
+    // Matching findings says nothing useful unless both versions have some
+    if old_findings.is_empty() || new_findings.is_empty() {
+        let lines = diff_lines(old, new);
+        if lines.is_empty() {
+            println!("    {}", "(no changes)".dimmed());
+        }
+        for (sign, line) in &lines {
+            match sign {
+                '-' => println!("    {}", format!("- {}", line).red()),
+                _ => println!("    {}", format!("+ {}", line).green()),
+            }
+        }
+        return (0, 0, 0);
+    }
+
+    let diff = diff_findings(&old_findings, &new_findings);
+    for f in &diff.resolved {
+        println!("    {} {}", "- resolved ".red(), format_finding(f));
+    }
+    for (_, f) in &diff.persisted {
+        println!("    {} {}", "= persisted".yellow(), format_finding(f));
+    }
+    for f in &diff.new {
+        println!("    {} {}", "+ new      ".green(), format_finding(f));
+    }
+    (diff.resolved.len(), diff.persisted.len(), diff.new.len())
+}
+
+/// Compare two runs member by member (aligned on constraint name) and synthesis to synthesis.
+pub fn print_run_diff(a: &Run, b: &Run) {
+    let (ida, idb) = (a.id.unwrap_or_default(), b.id.unwrap_or_default());
+
+    println!();
+    println!("{}", "=".repeat(60).green());
+    println!("{}", format!("              COUNCIL DIFF: #{} -> #{}", ida, idb).green().bold());
+    println!("{}", "=".repeat(60).green());
+    println!();
+    println!("  {}: {}", format!("Run #{}", ida).cyan(), truncate_chars(&a.task, 50));
+    println!("  {}: {}", format!("Run #{}", idb).cyan(), truncate_chars(&b.task, 50));
+
+    let mut totals = (0, 0, 0);
+    let mut names: Vec<&str> = a.members.iter().map(|m| m.name.as_str()).collect();
+    for m in &b.members {
+        if !names.contains(&m.name.as_str()) {
+            names.push(&m.name);
+        }
+    }
+
+    for name in names {
+        let old = a.members.iter().find(|m| m.name == name && m.status.has_output());
+        let new = b.members.iter().find(|m| m.name == name && m.status.has_output());
+
+        println!();
+        println!("{}", "-".repeat(60).blue());
+        match (old, new) {
+            (Some(_), Some(_)) => println!("  {}", name.to_uppercase().blue().bold()),
+            (Some(_), None) => println!("  {} {}", name.to_uppercase().blue().bold(), format!("(only in #{})", ida).dimmed()),
+            (None, Some(_)) => println!("  {} {}", name.to_uppercase().blue().bold(), format!("(only in #{})", idb).dimmed()),
+            (None, None) => println!("  {} {}", name.to_uppercase().blue().bold(), "(no output in either run)".dimmed()),
+        }
+        println!("{}", "-".repeat(60).blue());
+
+        // A lens present in only one run was not asked in the other, so nothing was resolved or added
+        let (Some(old), Some(new)) = (old, new) else {
+            let findings = old.or(new).map(|m| extract_findings(&m.text)).unwrap_or_default();
+            for f in &findings {
+                println!("    {} {}", "·".dimmed(), format_finding(f).dimmed());
+            }
+            continue;
+        };
+        let (r, p, n) = print_text_diff(&old.text, &new.text);
+        totals = (totals.0 + r, totals.1 + p, totals.2 + n);
+    }
+
+    println!();
+    println!("{}", "=".repeat(60).magenta());
+    println!("{}", "                   SYNTHESIS DIFF".magenta().bold());
+    println!("{}", "=".repeat(60).magenta());
+    let synthesis_text = |run: &Run| run.synthesis.as_ref().and_then(|s| s.text.as_ref().ok()).cloned().unwrap_or_default();
+    let (r, p, n) = print_text_diff(&synthesis_text(a), &synthesis_text(b));
+
+    println!();
+    println!("{}", "=".repeat(60).green());
+    println!(
+        "{}",
+        format!(
+            "  MEMBERS: {} resolved, {} persisted, {} new findings",
+            totals.0, totals.1, totals.2
+        )
+        .green()
+        .bold()
+    );
+    println!(
+        "{}",
+        format!("  SYNTHESIS: {} resolved, {} persisted, {} new findings", r, p, n).green().bold()
+    );
+    println!("{}", "=".repeat(60).green());
+}
+
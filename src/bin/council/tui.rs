@@ -0,0 +1,364 @@
+//! Full-screen TUI (`--tui`): a member list on the left, the selected member's output on the right.
+
+use crate::render::truncate_chars;
+use council::{MemberOutput, MemberStatus, Run, StreamProgress, SynthesisOutput};
+use ratatui::crossterm::event::{self, Event as TermEvent, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::style::{Color as TermColor, Modifier, Style};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// One member, or the synthesis, as a TUI panel.
+struct TuiPanel {
+    title: String,
+    model: String,
+    /// Final status; None while waiting or running
+    status: Option<MemberStatus>,
+    running: bool,
+    cached: bool,
+    started: Option<Instant>,
+    elapsed: Option<Duration>,
+    tool: Option<String>,
+    text: String,
+}
+
+impl TuiPanel {
+    fn state(&self) -> (&'static str, TermColor) {
+        match self.status {
+            _ if self.running => ("running", TermColor::Yellow),
+            Some(MemberStatus::Success) if self.cached => ("cached", TermColor::Cyan),
+            Some(MemberStatus::Success) => ("done", TermColor::Green),
+            Some(MemberStatus::Failed) => ("failed", TermColor::Red),
+            Some(MemberStatus::TimedOut) => ("timed out", TermColor::Red),
+            Some(MemberStatus::Cancelled) => ("cancelled", TermColor::Red),
+            Some(MemberStatus::Pending) | None => ("waiting", TermColor::DarkGray),
+        }
+    }
+
+    fn elapsed_label(&self) -> String {
+        match (self.elapsed, self.started) {
+            (Some(d), _) => format!("{:.1}s", d.as_secs_f64()),
+            (None, Some(started)) => format!("{}s", started.elapsed().as_secs()),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Full-screen view of a run: a member list on the left, the selected member's output on the
+/// right. The terminal is restored when it is dropped.
+pub struct Tui {
+    terminal: ratatui::Terminal<ratatui::backend::CrosstermBackend<std::io::Stdout>>,
+    task: String,
+    /// Members in roster order, then the synthesis if the run has one
+    panels: Vec<TuiPanel>,
+    has_synthesis: bool,
+    selected: usize,
+    /// Lines scrolled up from the bottom of the selected panel; 0 follows new output
+    scroll_back: usize,
+    page_height: usize,
+    pub banner: String,
+    /// Tells the key reader thread to stop
+    stop_keys: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+/// Wrap `text` to `width` columns, breaking at spaces where possible.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for raw in text.lines() {
+        let raw: String = raw.replace('\t', "    ").chars().filter(|c| !c.is_control()).collect();
+        let mut line = String::new();
+        let mut len = 0;
+        for word in raw.split_inclusive(' ') {
+            let word_len = word.chars().count();
+            if len > 0 && len + word_len > width {
+                lines.push(line.trim_end().to_string());
+                line.clear();
+                len = 0;
+            }
+            for c in word.chars() {
+                if len == width {
+                    lines.push(std::mem::take(&mut line));
+                    len = 0;
+                }
+                line.push(c);
+                len += 1;
+            }
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    lines
+}
+
+impl Tui {
+    /// Take over the terminal. Keys arrive on the returned channel from a reader thread.
+    pub fn start(run: &Run, follow: Option<usize>) -> Result<(Tui, mpsc::UnboundedReceiver<TermEvent>), String> {
+        enable_raw_mode().map_err(|e| format!("Cannot start the TUI: {}", e))?;
+        let mut stdout = std::io::stdout();
+        if let Err(e) = execute!(stdout, EnterAlternateScreen) {
+            let _ = disable_raw_mode();
+            return Err(format!("Cannot start the TUI: {}", e));
+        }
+        let terminal = match ratatui::Terminal::new(ratatui::backend::CrosstermBackend::new(stdout)) {
+            Ok(t) => t,
+            Err(e) => {
+                let _ = execute!(std::io::stdout(), LeaveAlternateScreen);
+                let _ = disable_raw_mode();
+                return Err(format!("Cannot start the TUI: {}", e));
+            }
+        };
+
+        let model = |m: Option<&str>| m.unwrap_or("default").to_string();
+        let mut panels: Vec<TuiPanel> = run
+            .members
+            .iter()
+            .map(|m| TuiPanel {
+                title: format!("#{} {}", m.id + 1, m.name.to_uppercase()),
+                model: model(m.model.as_deref()),
+                status: (m.status != MemberStatus::Pending).then_some(m.status),
+                running: false,
+                cached: m.cached,
+                started: None,
+                elapsed: (m.status != MemberStatus::Pending).then_some(m.elapsed),
+                tool: None,
+                text: m.text.clone(),
+            })
+            .collect();
+        let has_synthesis = run.settings.synthesize;
+        if has_synthesis {
+            panels.push(TuiPanel {
+                title: "SYNTHESIS".to_string(),
+                model: model(run.settings.synthesis_model.as_deref().or(run.settings.model.as_deref())),
+                status: None,
+                running: false,
+                cached: false,
+                started: None,
+                elapsed: None,
+                tool: None,
+                text: String::new(),
+            });
+        }
+
+        let stop_keys = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (key_tx, key_rx) = mpsc::unbounded_channel();
+        let stop = stop_keys.clone();
+        std::thread::spawn(move || {
+            while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+                if event::poll(Duration::from_millis(100)).unwrap_or(false) {
+                    let Ok(ev) = event::read() else { break };
+                    if key_tx.send(ev).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let tui = Tui {
+            terminal,
+            task: truncate_chars(&run.task, 200),
+            panels,
+            has_synthesis,
+            selected: follow.unwrap_or(0),
+            scroll_back: 0,
+            page_height: 0,
+            banner: "Members running".to_string(),
+            stop_keys,
+        };
+        Ok((tui, key_rx))
+    }
+
+    fn synthesis_panel(&mut self) -> Option<&mut TuiPanel> {
+        if self.has_synthesis {
+            self.panels.last_mut()
+        } else {
+            None
+        }
+    }
+
+    pub fn member_started(&mut self, id: usize) {
+        if let Some(p) = self.panels.get_mut(id) {
+            p.running = true;
+            p.started = Some(Instant::now());
+        }
+    }
+
+    pub fn member_finished(&mut self, m: &MemberOutput) {
+        if let Some(p) = self.panels.get_mut(m.id) {
+            p.running = false;
+            p.status = Some(m.status);
+            p.cached = m.cached;
+            p.elapsed = Some(m.elapsed);
+            p.tool = None;
+            p.text = m.text.clone();
+        }
+        let done = self.panels.iter().take(self.panels.len() - self.has_synthesis as usize);
+        let finished = done.filter(|p| p.status.is_some()).count();
+        self.banner = format!("{} of {} members finished", finished, self.panels.len() - self.has_synthesis as usize);
+    }
+
+    /// Stream progress of member `id`, or of the synthesis when `id` is None.
+    pub fn progress(&mut self, id: Option<usize>, progress: &StreamProgress) {
+        let panel = match id {
+            Some(id) => self.panels.get_mut(id),
+            None => self.synthesis_panel(),
+        };
+        if let Some(p) = panel {
+            p.tool = progress.tool.clone();
+            if let Some(text) = &progress.text {
+                if !p.text.is_empty() && !p.text.ends_with('\n') {
+                    p.text.push('\n');
+                }
+                p.text.push_str(text);
+            }
+        }
+    }
+
+    pub fn synthesis_started(&mut self) {
+        self.banner = "Synthesizing".to_string();
+        if let Some(p) = self.synthesis_panel() {
+            p.running = true;
+            p.started = Some(Instant::now());
+        }
+    }
+
+    pub fn synthesis_finished(&mut self, s: &SynthesisOutput) {
+        self.banner = "Done".to_string();
+        if let Some(p) = self.synthesis_panel() {
+            p.running = false;
+            p.tool = None;
+            p.elapsed = Some(s.elapsed);
+            match &s.text {
+                Ok(text) => {
+                    p.status = Some(MemberStatus::Success);
+                    p.text = text.clone();
+                }
+                Err(e) => {
+                    p.status = Some(MemberStatus::Failed);
+                    p.text = format!("Synthesis failed: {}", e);
+                }
+            }
+        }
+        if self.has_synthesis {
+            self.selected = self.panels.len() - 1;
+            self.scroll_back = 0;
+        }
+    }
+
+    /// Handle a terminal event; true when the user asked to quit.
+    pub fn key(&mut self, event: TermEvent) -> bool {
+        let TermEvent::Key(key) = event else {
+            self.draw();
+            return false;
+        };
+        if key.kind != KeyEventKind::Press {
+            return false;
+        }
+        let page = self.page_height.max(1);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::BackTab => {
+                self.selected = self.selected.checked_sub(1).unwrap_or(self.panels.len() - 1);
+                self.scroll_back = 0;
+            }
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => {
+                self.selected = (self.selected + 1) % self.panels.len();
+                self.scroll_back = 0;
+            }
+            KeyCode::Char('s') if self.has_synthesis => {
+                self.selected = self.panels.len() - 1;
+                self.scroll_back = 0;
+            }
+            KeyCode::PageUp | KeyCode::Char('b') => self.scroll_back += page,
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll_back = self.scroll_back.saturating_sub(page),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll_back = usize::MAX,
+            KeyCode::End | KeyCode::Char('G') => self.scroll_back = 0,
+            _ => return false,
+        }
+        self.draw();
+        false
+    }
+
+    pub fn draw(&mut self) {
+        use ratatui::layout::{Constraint as Size, Layout};
+        use ratatui::text::{Line, Span};
+        use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+
+        let Tui { terminal, task, panels, selected, scroll_back, page_height, banner, .. } = self;
+        let _ = terminal.draw(|frame| {
+            let [header, body, footer] =
+                Layout::vertical([Size::Length(1), Size::Min(3), Size::Length(1)]).areas(frame.area());
+            let [list_area, detail_area] = Layout::horizontal([Size::Length(48), Size::Min(20)]).areas(body);
+
+            frame.render_widget(
+                Paragraph::new(Line::from(vec![
+                    Span::styled(" COUNCIL ", Style::default().fg(TermColor::Black).bg(TermColor::Green)),
+                    Span::raw(" "),
+                    Span::raw(task.as_str()),
+                ])),
+                header,
+            );
+
+            let items: Vec<ListItem> = panels
+                .iter()
+                .map(|p| {
+                    let (label, color) = p.state();
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{:<10}", label), Style::default().fg(color)),
+                        Span::raw(format!("{:<28} ", p.title)),
+                        Span::styled(p.elapsed_label(), Style::default().fg(TermColor::DarkGray)),
+                    ]))
+                })
+                .collect();
+            let mut state = ListState::default().with_selected(Some(*selected));
+            frame.render_stateful_widget(
+                List::new(items)
+                    .block(Block::bordered().title(" Council "))
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+                list_area,
+                &mut state,
+            );
+
+            let panel = &panels[*selected];
+            let height = detail_area.height.saturating_sub(2) as usize;
+            let lines = wrap_text(&panel.text, detail_area.width.saturating_sub(2) as usize);
+            let max_top = lines.len().saturating_sub(height);
+            *scroll_back = (*scroll_back).min(max_top);
+            *page_height = height;
+            let top = max_top - *scroll_back;
+            let visible: Vec<Line> = lines[top..(top + height).min(lines.len())].iter().map(|l| Line::raw(l.as_str())).collect();
+
+            let (label, color) = panel.state();
+            let mut title = vec![
+                Span::raw(format!(" {} ", panel.title)),
+                Span::styled(label, Style::default().fg(color)),
+                Span::raw(format!(" {} · {} ", panel.elapsed_label(), panel.model)),
+            ];
+            if let Some(tool) = &panel.tool {
+                title.push(Span::styled(format!("· {} ", tool), Style::default().fg(TermColor::Cyan)));
+            }
+            frame.render_widget(Paragraph::new(visible).block(Block::bordered().title(Line::from(title))), detail_area);
+
+            frame.render_widget(
+                Paragraph::new(Line::from(vec![
+                    Span::styled(format!(" {} ", banner), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(
+                        " ↑↓ member · PgUp/PgDn scroll · Home/End · s synthesis · q quit",
+                        Style::default().fg(TermColor::DarkGray),
+                    ),
+                ])),
+                footer,
+            );
+        });
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        self.stop_keys.store(true, std::sync::atomic::Ordering::Relaxed);
+        let _ = disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
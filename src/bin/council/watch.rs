@@ -0,0 +1,136 @@
+//! `council watch`: re-run a council whenever watched files change, and show which findings the
+//! changes resolved.
+
+use crate::frontend::{watch_council, ViewOptions};
+use crate::render::print_text_diff;
+use colored::*;
+use council::{
+    backend_installed, changed_files, merge_rerun, new_run, relevant_lenses, rerun, snapshot, Config, Council, Run,
+    Snapshot,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How often the watched files are checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Options for `council watch`.
+pub struct WatchOptions {
+    pub paths: Vec<PathBuf>,
+    pub task: String,
+    /// How long files must stay unchanged before a run starts
+    pub debounce: Duration,
+    /// Re-run only the lenses whose findings cite a changed file
+    pub relevant: bool,
+    pub all: bool,
+}
+
+/// Wait until files under `paths` change and then stay unchanged for `debounce`, returning
+/// what changed since `seen`, which is brought up to date.
+async fn next_change(paths: &[PathBuf], seen: &mut Snapshot, debounce: Duration) -> Vec<PathBuf> {
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let mut latest = snapshot(paths);
+        if latest == *seen {
+            continue;
+        }
+        loop {
+            tokio::time::sleep(debounce).await;
+            let again = snapshot(paths);
+            if again == latest {
+                break;
+            }
+            latest = again;
+        }
+        let changed = changed_files(seen, &latest);
+        *seen = latest;
+        if !changed.is_empty() {
+            return changed;
+        }
+    }
+}
+
+/// What `latest` resolved, kept and added compared with `state`: synthesis to synthesis when
+/// the whole roster ran, otherwise the re-run lenses against their previous output.
+fn print_iteration_diff(state: &Run, latest: &Run) {
+    let synthesis_text = |run: &Run| run.synthesis.as_ref().and_then(|s| s.text.as_ref().ok()).cloned();
+    let texts = match (synthesis_text(state), synthesis_text(latest)) {
+        (Some(old), Some(new)) if latest.members.len() == state.members.len() => (old, new),
+        _ => {
+            let mut old = String::new();
+            let mut new = String::new();
+            for m in latest.members.iter().filter(|m| m.status.has_output()) {
+                if let Some(prev) = state.members.iter().find(|s| s.name == m.name) {
+                    old.push_str(&prev.text);
+                    old.push('\n');
+                }
+                new.push_str(&m.text);
+                new.push('\n');
+            }
+            (old, new)
+        }
+    };
+
+    println!();
+    println!("{}", "=".repeat(60).green());
+    match state.id {
+        Some(id) => println!("{}", format!("              SINCE RUN #{}", id).green().bold()),
+        None => println!("{}", "              SINCE THE PREVIOUS RUN".green().bold()),
+    }
+    println!("{}", "=".repeat(60).green());
+    let (resolved, persisted, new) = print_text_diff(&texts.0, &texts.1);
+    println!();
+    println!(
+        "  {}",
+        format!("{} resolved, {} persisted, {} new findings", resolved, persisted, new).green().bold()
+    );
+}
+
+/// Run a council over `options.task`, then again every time the watched files change, until
+/// interrupted. A run that fails is reported and the watch goes on.
+pub async fn watch(config: &Config, options: WatchOptions) -> Result<(), String> {
+    for path in &options.paths {
+        if !path.exists() {
+            return Err(format!("Cannot watch {}: no such file or directory", path.display()));
+        }
+    }
+    if !backend_installed(&config.backend) {
+        return Err(format!("'{}' CLI not found in PATH", config.backend.command));
+    }
+    let mut config = config.clone();
+    config.tui = false;
+    let view = ViewOptions { all: options.all, ..ViewOptions::default() };
+    let watched: Vec<String> = options.paths.iter().map(|p| p.display().to_string()).collect();
+
+    let mut seen = snapshot(&options.paths);
+    let run = new_run(&config, options.task.clone())?;
+    let mut state = watch_council(Council::from_config(&config, run), &view).await?;
+
+    loop {
+        println!("\n{} {} (Ctrl-C to stop)", "Watching".cyan(), watched.join(", "));
+        let changed = next_change(&options.paths, &mut seen, options.debounce).await;
+        let listed: Vec<String> = changed.iter().map(|p| p.display().to_string()).collect();
+        println!("\n{} {}", "Changed:".cyan(), listed.join(", "));
+
+        let lenses = if options.relevant { relevant_lenses(&state, &changed) } else { Vec::new() };
+        if options.relevant && lenses.is_empty() {
+            println!("  No lens cited the changed files; re-running all of them");
+        } else if options.relevant {
+            println!("  Re-running the lenses that cited them: {}", lenses.join(", "));
+        }
+        let run = match rerun(&config, &options.task, &state, &changed, &lenses) {
+            Ok(run) => run,
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red().bold(), e);
+                continue;
+            }
+        };
+        match watch_council(Council::from_config(&config, run), &view).await {
+            Ok(latest) => {
+                print_iteration_diff(&state, &latest);
+                merge_rerun(&mut state, &latest);
+            }
+            Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+        }
+    }
+}
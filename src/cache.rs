@@ -0,0 +1,250 @@
+//! Member output cache: content-addressed entries under the XDG cache dir.
+
+use crate::backend::{Backend, Permission};
+use crate::run::{MemberOutput, MemberStatus, RunSettings};
+use crate::sandbox::{fingerprint, git, git_toplevel, Isolation};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Reuses successful member outputs for identical prompts against an identical tree.
+pub struct MemberCache {
+    dir: PathBuf,
+    /// Hash of the repository contents the members will see
+    tree_hash: String,
+    /// Members run by another CLI produce different outputs
+    backend: Backend,
+    /// What members may do, and where, changes what they can find
+    permissions: Permission,
+    isolation: Isolation,
+    /// Ignore existing entries but still store fresh ones
+    refresh: bool,
+}
+
+fn cache_dir() -> Result<PathBuf, String> {
+    if let Ok(dir) = std::env::var("XDG_CACHE_HOME") {
+        if !dir.is_empty() {
+            return Ok(PathBuf::from(dir).join("council").join("members"));
+        }
+    }
+    let home = std::env::var("HOME").map_err(|_| "Cannot determine home directory".to_string())?;
+    Ok(PathBuf::from(home).join(".cache").join("council").join("members"))
+}
+
+fn hex_digest(hasher: Sha256) -> String {
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hash everything a member could read in `dir`: committed tree, uncommitted diff and
+/// untracked files inside git, or every file's contents outside it.
+fn repo_tree_hash(dir: &Path) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    match git_toplevel(dir) {
+        Some(top) => {
+            // An unborn HEAD has no tree; the diff and untracked files still cover everything
+            hasher.update(git(&top, &["rev-parse", "HEAD^{tree}"]).unwrap_or_default());
+            hasher.update(git(&top, &["diff", "HEAD", "--binary"]).unwrap_or_default());
+            let untracked = git(&top, &["ls-files", "-z", "--others", "--exclude-standard"])?;
+            for rel in untracked.split(|b| *b == 0).filter(|p| !p.is_empty()) {
+                hasher.update(rel);
+                hasher.update(std::fs::read(top.join(String::from_utf8_lossy(rel).as_ref())).unwrap_or_default());
+            }
+        }
+        None => {
+            let mut files: Vec<_> = fingerprint(dir).into_iter().collect();
+            files.sort();
+            for (rel, hash) in files {
+                hasher.update(rel.to_string_lossy().as_bytes());
+                hasher.update(hash.to_le_bytes());
+            }
+        }
+    }
+    Ok(hex_digest(hasher))
+}
+
+impl MemberCache {
+    /// Open the cache for members run with `settings` in `workdir`, hashing the tree they will see.
+    pub fn open(workdir: &Path, settings: &RunSettings, refresh: bool) -> Result<MemberCache, String> {
+        let dir = cache_dir()?;
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+        Ok(MemberCache {
+            dir,
+            tree_hash: repo_tree_hash(workdir)?,
+            backend: settings.backend.clone(),
+            permissions: settings.permissions,
+            isolation: settings.isolation,
+            refresh,
+        })
+    }
+
+    fn key(&self, m: &MemberOutput) -> String {
+        let mut hasher = Sha256::new();
+        let backend = self.backend.describe();
+        let parts = [
+            m.prompt.as_str(),
+            m.model.as_deref().unwrap_or(""),
+            &backend,
+            self.permissions.as_str(),
+            self.isolation.as_str(),
+            &self.tree_hash,
+        ];
+        for part in parts {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        hex_digest(hasher)
+    }
+
+    /// Fill in `m` from the cache if an entry exists.
+    pub fn load(&self, m: &mut MemberOutput) -> bool {
+        if self.refresh {
+            return false;
+        }
+        let path = self.dir.join(format!("{}.json", self.key(m)));
+        let Some(entry) = std::fs::read(&path).ok().and_then(|b| serde_json::from_slice::<serde_json::Value>(&b).ok())
+        else {
+            return false;
+        };
+        let Some(text) = entry["text"].as_str() else { return false };
+        m.status = MemberStatus::Success;
+        m.text = text.to_string();
+        m.cached = true;
+        true
+    }
+
+    /// Save a successful member's output.
+    pub fn store(&self, m: &MemberOutput) -> Result<(), String> {
+        let path = self.dir.join(format!("{}.json", self.key(m)));
+        let entry = serde_json::json!({
+            "name": m.name,
+            "model": m.model,
+            "text": m.text,
+            "input_tokens": m.usage.input_tokens,
+            "output_tokens": m.usage.output_tokens,
+            "cost_usd": m.usage.cost_usd,
+        });
+        std::fs::write(&path, entry.to_string()).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Usage;
+    use std::time::Duration;
+
+    fn scratch(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("council-cache-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A cache in `dir` that is not read from the environment, so tests can run side by side.
+    fn cache(dir: &Path) -> MemberCache {
+        MemberCache {
+            dir: dir.to_path_buf(),
+            tree_hash: "tree".to_string(),
+            backend: Backend::default(),
+            permissions: Permission::ReadOnly,
+            isolation: Isolation::None,
+            refresh: false,
+        }
+    }
+
+    fn member(prompt: &str) -> MemberOutput {
+        MemberOutput {
+            id: 0,
+            name: "knuth".to_string(),
+            model: None,
+            prompt: prompt.to_string(),
+            status: MemberStatus::Pending,
+            text: String::new(),
+            usage: Usage::default(),
+            elapsed: Duration::ZERO,
+            cached: false,
+        }
+    }
+
+    #[test]
+    fn key_covers_everything_that_changes_the_output() {
+        let dir = scratch("key");
+        let base = cache(&dir);
+        let key = base.key(&member("review"));
+        assert_eq!(key, cache(&dir).key(&member("review")));
+
+        let mut other_model = member("review");
+        other_model.model = Some("opus".to_string());
+        let variants = [
+            base.key(&member("review it")),
+            base.key(&other_model),
+            MemberCache { permissions: Permission::Full, ..cache(&dir) }.key(&member("review")),
+            MemberCache { isolation: Isolation::Worktree, ..cache(&dir) }.key(&member("review")),
+            MemberCache { tree_hash: "edited".to_string(), ..cache(&dir) }.key(&member("review")),
+            MemberCache { backend: Backend { command: "other".to_string(), args: Vec::new() }, ..cache(&dir) }
+                .key(&member("review")),
+        ];
+        for variant in &variants {
+            assert_ne!(*variant, key);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stored_output_is_loaded_unless_refreshing() {
+        let dir = scratch("store");
+        let mut done = member("review");
+        done.status = MemberStatus::Success;
+        done.text = "- P1 found it".to_string();
+        cache(&dir).store(&done).unwrap();
+
+        let mut hit = member("review");
+        assert!(cache(&dir).load(&mut hit));
+        assert_eq!((hit.status, hit.text.as_str(), hit.cached), (MemberStatus::Success, "- P1 found it", true));
+
+        assert!(!cache(&dir).load(&mut member("another prompt")));
+        assert!(!MemberCache { refresh: true, ..cache(&dir) }.load(&mut member("review")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tree_hash_follows_file_contents() {
+        let dir = scratch("tree");
+        std::fs::write(dir.join("a.rs"), "fn a() {}\n").unwrap();
+        let before = repo_tree_hash(&dir).unwrap();
+        assert_eq!(repo_tree_hash(&dir).unwrap(), before);
+
+        // Build output is not something a member's answer depends on
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        std::fs::write(dir.join("target/out"), "binary").unwrap();
+        assert_eq!(repo_tree_hash(&dir).unwrap(), before);
+
+        std::fs::write(dir.join("a.rs"), "fn b() {}\n").unwrap();
+        let edited = repo_tree_hash(&dir).unwrap();
+        assert_ne!(edited, before);
+        std::fs::write(dir.join("b.rs"), "").unwrap();
+        assert_ne!(repo_tree_hash(&dir).unwrap(), edited);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tree_hash_in_git_sees_uncommitted_and_untracked_files() {
+        let dir = scratch("git");
+        std::fs::write(dir.join("a.rs"), "fn a() {}\n").unwrap();
+        let git = |args: &[&str]| {
+            let mut full = vec!["-c", "user.name=council", "-c", "user.email=council@example.com"];
+            full.extend_from_slice(args);
+            git(&dir, &full).unwrap();
+        };
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "init"]);
+
+        let committed = repo_tree_hash(&dir).unwrap();
+        std::fs::write(dir.join("a.rs"), "fn b() {}\n").unwrap();
+        let edited = repo_tree_hash(&dir).unwrap();
+        assert_ne!(edited, committed);
+        std::fs::write(dir.join("new.rs"), "fn new() {}\n").unwrap();
+        assert_ne!(repo_tree_hash(&dir).unwrap(), edited);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
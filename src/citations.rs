@@ -0,0 +1,231 @@
+//! Citation checks: whether the `path:line` references in member and synthesis output point at
+//! lines that exist in the working tree.
+
+use crate::findings::{find_locations, Location};
+use crate::run::Run;
+use crate::sandbox::{git_toplevel, git_visible_files, walk_files};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// One `path:line` reference and, if it does not hold up, why.
+#[derive(Debug, Clone)]
+pub struct Citation {
+    pub location: Location,
+    pub problem: Option<String>,
+}
+
+/// The distinct citations of one analysis or of the synthesis.
+pub struct SourceCitations {
+    /// Member name, or "synthesis"
+    pub name: String,
+    /// Member index, or `None` for the synthesis
+    pub member: Option<usize>,
+    pub citations: Vec<Citation>,
+}
+
+impl SourceCitations {
+    pub fn valid(&self) -> usize {
+        self.citations.iter().filter(|c| c.problem.is_none()).count()
+    }
+
+    /// Share of citations that hold up, or `None` without any.
+    pub fn accuracy(&self) -> Option<f64> {
+        (!self.citations.is_empty()).then(|| self.valid() as f64 / self.citations.len() as f64)
+    }
+}
+
+/// Resolves cited paths against a run's working tree, reading each file once.
+struct Tree {
+    workdir: PathBuf,
+    toplevel: Option<PathBuf>,
+    /// Every file under the working directory, for citations that leave out leading directories
+    files: Vec<PathBuf>,
+    line_counts: HashMap<PathBuf, Option<u32>>,
+}
+
+impl Tree {
+    fn new(workdir: &Path) -> Tree {
+        let files = git_visible_files(workdir).unwrap_or_else(|_| {
+            let mut files = Vec::new();
+            walk_files(workdir, workdir, &mut files);
+            files
+        });
+        Tree {
+            workdir: workdir.to_path_buf(),
+            toplevel: git_toplevel(workdir),
+            files,
+            line_counts: HashMap::new(),
+        }
+    }
+
+    /// The file `cited` refers to: relative to the working directory or the repository root,
+    /// or else the only file whose path ends with it.
+    fn resolve(&self, cited: &str) -> Option<PathBuf> {
+        let cited = Path::new(cited);
+        let mut candidates = vec![self.workdir.join(cited)];
+        if let Some(top) = &self.toplevel {
+            candidates.push(top.join(cited));
+        }
+        if let Some(found) = candidates.into_iter().find(|p| p.is_file()) {
+            return Some(found);
+        }
+        let mut matches = self.files.iter().filter(|f| f.ends_with(cited));
+        match (matches.next(), matches.next()) {
+            (Some(only), None) => Some(self.workdir.join(only)),
+            _ => None,
+        }
+    }
+
+    fn line_count(&mut self, file: &Path) -> Option<u32> {
+        *self.line_counts.entry(file.to_path_buf()).or_insert_with(|| {
+            let bytes = std::fs::read(file).ok()?;
+            let newlines = bytes.iter().filter(|b| **b == b'\n').count();
+            // A last line without a newline still counts
+            Some((newlines + usize::from(!bytes.is_empty() && !bytes.ends_with(b"\n"))) as u32)
+        })
+    }
+
+    fn check(&mut self, location: &Location) -> Option<String> {
+        let Some(file) = self.resolve(&location.path) else {
+            return Some("no such file".to_string());
+        };
+        let Some(lines) = self.line_count(&file) else {
+            return Some("file cannot be read".to_string());
+        };
+        let last = location.end_line.unwrap_or(location.line).max(location.line);
+        if location.line == 0 {
+            Some("lines start at 1".to_string())
+        } else if last > lines {
+            Some(format!("the file has {} lines", lines))
+        } else {
+            None
+        }
+    }
+}
+
+/// Check every distinct citation of each analysis with output and of the synthesis against
+/// the run's working directory as it is now.
+pub fn verify_citations(run: &Run) -> Vec<SourceCitations> {
+    let mut tree = Tree::new(&run.workdir);
+    let mut check = |text: &str| {
+        let mut locations = find_locations(text);
+        let mut seen = Vec::new();
+        locations.retain(|l| {
+            let new = !seen.contains(l);
+            seen.push(l.clone());
+            new
+        });
+        locations
+            .into_iter()
+            .map(|location| {
+                let problem = tree.check(&location);
+                Citation { location, problem }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut sources: Vec<SourceCitations> = run
+        .members
+        .iter()
+        .filter(|m| m.status.has_output())
+        .map(|m| SourceCitations { name: m.name.clone(), member: Some(m.id), citations: check(&m.text) })
+        .collect();
+    if let Some(Ok(text)) = run.synthesis.as_ref().map(|s| &s.text) {
+        sources.push(SourceCitations { name: "synthesis".to_string(), member: None, citations: check(text) });
+    }
+    sources
+}
+
+/// Whether `c` can continue a path, so a reference found next to it is part of a longer one.
+fn path_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/')
+}
+
+/// `text` with a note after every citation in `citations` that does not hold up.
+pub fn annotate_citations(text: &str, citations: &[Citation]) -> String {
+    let mut notes: Vec<(usize, String)> = Vec::new();
+    for c in citations {
+        let Some(problem) = &c.problem else { continue };
+        let reference = c.location.to_string();
+        for (start, _) in text.match_indices(&reference) {
+            let before = text[..start].trim_end_matches("./").chars().next_back();
+            let mut end = start + reference.len();
+            let after = text[end..].chars().next();
+            if before.is_some_and(path_char) || after.is_some_and(|ch| ch.is_ascii_digit() || ch == '-') {
+                continue;
+            }
+            if after == Some('`') {
+                end += 1;
+            }
+            notes.push((end, format!(" _(invalid citation: {})_", problem)));
+        }
+    }
+    notes.sort_by_key(|(at, _)| *at);
+
+    let mut annotated = String::with_capacity(text.len());
+    let mut from = 0;
+    for (at, note) in notes {
+        annotated.push_str(&text[from..at]);
+        annotated.push_str(&note);
+        from = at;
+    }
+    annotated.push_str(&text[from..]);
+    annotated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(path: &str, line: u32, end_line: Option<u32>) -> Location {
+        Location { path: path.to_string(), line, end_line }
+    }
+
+    /// A fresh directory holding `src/lib.rs` (3 lines, the last without a newline) and
+    /// `docs/guide.md` (1 line).
+    fn sample_tree(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("council-citations-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}\nfn b() {}\nfn c() {}").unwrap();
+        std::fs::write(dir.join("docs/guide.md"), "# Guide\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn check_accepts_lines_inside_the_file() {
+        let dir = sample_tree("inside");
+        let mut tree = Tree::new(&dir);
+        assert_eq!(tree.check(&location("src/lib.rs", 3, None)), None);
+        assert_eq!(tree.check(&location("src/lib.rs", 1, Some(3))), None);
+        // Leading directories may be left out when only one file matches
+        assert_eq!(tree.check(&location("guide.md", 1, None)), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_explains_bad_citations() {
+        let dir = sample_tree("bad");
+        let mut tree = Tree::new(&dir);
+        assert_eq!(tree.check(&location("src/lib.rs", 4, None)).as_deref(), Some("the file has 3 lines"));
+        assert_eq!(tree.check(&location("src/lib.rs", 2, Some(9))).as_deref(), Some("the file has 3 lines"));
+        assert_eq!(tree.check(&location("src/lib.rs", 0, None)).as_deref(), Some("lines start at 1"));
+        assert_eq!(tree.check(&location("src/main.rs", 1, None)).as_deref(), Some("no such file"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn annotate_marks_only_whole_invalid_references() {
+        let citations = [
+            Citation { location: location("src/lib.rs", 9, None), problem: Some("the file has 3 lines".to_string()) },
+            Citation { location: location("src/lib.rs", 2, None), problem: None },
+        ];
+        let text = "See `src/lib.rs:9`, src/lib.rs:2, src/lib.rs:90 and old/src/lib.rs:9.";
+        assert_eq!(
+            annotate_citations(text, &citations),
+            "See `src/lib.rs:9` _(invalid citation: the file has 3 lines)_, src/lib.rs:2, src/lib.rs:90 and \
+             old/src/lib.rs:9."
+        );
+    }
+}
@@ -0,0 +1,551 @@
+//! Layered configuration: defaults, config files, profiles and `COUNCIL_*` variables.
+
+use crate::backend::{Backend, Permission};
+use crate::prompt::SYNTHESIS_TEMPLATES;
+use crate::sandbox::{git_toplevel, Isolation};
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Every setting a config file, profile or `COUNCIL_*` variable may set, in display order.
+pub const CONFIG_KEYS: [&str; 21] = [
+    "num", "timeout", "model", "permissions", "synthesis_permissions", "isolate", "synthesize",
+    "all", "tui", "synthesis_template", "member_template", "file", "max_cost", "max_tokens", "report",
+    "cache", "refresh", "seed", "history", "backend.command", "backend.args",
+];
+
+/// Profiles available without any config file; files may override or extend them.
+const BUILTIN_PROFILES: &str = r#"
+[quick]
+num = 3
+model = "haiku"
+timeout = 300
+
+[deep]
+num = 8
+model = "opus"
+timeout = 1800
+all = true
+"#;
+
+/// Settings for a new run after layering defaults, config files, a profile, the environment
+/// and CLI flags, each remembering which layer set it.
+#[derive(Clone)]
+pub struct Config {
+    pub num: usize,
+    pub timeout: u64,
+    pub model: Option<String>,
+    pub permissions: Permission,
+    pub synthesis_permissions: Permission,
+    pub isolate: Isolation,
+    pub synthesize: bool,
+    pub all: bool,
+    pub tui: bool,
+    pub synthesis_template: Option<String>,
+    pub member_template: Vec<String>,
+    pub file: Vec<PathBuf>,
+    pub max_cost: Option<f64>,
+    pub max_tokens: Option<u64>,
+    pub report: Option<PathBuf>,
+    pub cache: bool,
+    pub refresh: bool,
+    pub seed: Option<u64>,
+    pub history: bool,
+    pub backend: Backend,
+    /// Selected profile and the layer that selected it
+    pub profile: Option<(String, String)>,
+    /// Config files that were found and read
+    pub files: Vec<PathBuf>,
+    /// Layer that last set each key; keys absent here are defaults
+    pub sources: HashMap<&'static str, String>,
+    /// Settings the repository config may not make, which were ignored
+    pub warnings: Vec<String>,
+}
+
+/// A raw setting: typed from a TOML file, or a string from the environment.
+enum ConfigValue<'a> {
+    Toml(&'a toml::Value),
+    Env(&'a str),
+}
+
+impl ConfigValue<'_> {
+    fn string(&self) -> Result<String, String> {
+        match self {
+            ConfigValue::Toml(toml::Value::String(s)) => Ok(s.clone()),
+            ConfigValue::Toml(v) => Err(format!("expected a string, got {}", v)),
+            ConfigValue::Env(s) => Ok(s.to_string()),
+        }
+    }
+
+    fn boolean(&self) -> Result<bool, String> {
+        match self {
+            ConfigValue::Toml(toml::Value::Boolean(b)) => Ok(*b),
+            ConfigValue::Toml(v) => Err(format!("expected true or false, got {}", v)),
+            ConfigValue::Env(s) => match s.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Ok(true),
+                "0" | "false" | "no" | "off" | "" => Ok(false),
+                _ => Err(format!("expected true or false, got '{}'", s)),
+            },
+        }
+    }
+
+    fn integer(&self) -> Result<u64, String> {
+        match self {
+            ConfigValue::Toml(toml::Value::Integer(i)) if *i >= 0 => Ok(*i as u64),
+            ConfigValue::Toml(v) => Err(format!("expected a non-negative integer, got {}", v)),
+            ConfigValue::Env(s) => s.trim().parse().map_err(|_| format!("expected a non-negative integer, got '{}'", s)),
+        }
+    }
+
+    fn float(&self) -> Result<f64, String> {
+        match self {
+            ConfigValue::Toml(toml::Value::Float(f)) => Ok(*f),
+            ConfigValue::Toml(toml::Value::Integer(i)) => Ok(*i as f64),
+            ConfigValue::Toml(v) => Err(format!("expected a number, got {}", v)),
+            ConfigValue::Env(s) => s.trim().parse().map_err(|_| format!("expected a number, got '{}'", s)),
+        }
+    }
+
+    /// A TOML array of strings, or a comma-separated environment value.
+    fn list(&self) -> Result<Vec<String>, String> {
+        match self {
+            ConfigValue::Toml(toml::Value::Array(items)) => items
+                .iter()
+                .map(|v| v.as_str().map(str::to_string).ok_or_else(|| format!("expected strings, got {}", v)))
+                .collect(),
+            ConfigValue::Toml(v) => Err(format!("expected an array of strings, got {}", v)),
+            ConfigValue::Env(s) => Ok(s.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect()),
+        }
+    }
+}
+
+pub fn config_dir() -> Result<PathBuf, String> {
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        if !dir.is_empty() {
+            return Ok(PathBuf::from(dir).join("council"));
+        }
+    }
+    let home = std::env::var("HOME").map_err(|_| "Cannot determine home directory".to_string())?;
+    Ok(PathBuf::from(home).join(".config").join("council"))
+}
+
+/// The nearest `.council.toml` from `cwd` up to the repository root (just `cwd` outside git).
+fn repo_config_path(cwd: &Path) -> Option<PathBuf> {
+    let top = git_toplevel(cwd);
+    for dir in cwd.ancestors() {
+        let path = dir.join(".council.toml");
+        if path.is_file() {
+            return Some(path);
+        }
+        if top.as_deref().is_none_or(|t| t == dir) {
+            break;
+        }
+    }
+    None
+}
+
+fn read_config_file(path: &Path) -> Result<toml::Table, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read config {}: {}", path.display(), e))?;
+    text.parse::<toml::Table>().map_err(|e| format!("Invalid config {}: {}", path.display(), e.message()))
+}
+
+/// Whether `key = value` reaches outside the repository a config in `base` belongs to: choosing
+/// the backend, granting full permissions, or naming files elsewhere on the host (read into
+/// prompts, or overwritten with the report). A repository config may not do that.
+fn restricted(key: &str, value: &toml::Value, base: Option<&Path>) -> bool {
+    let paths: Vec<&str> = match value {
+        toml::Value::String(s) => vec![s.as_str()],
+        toml::Value::Array(items) => items.iter().filter_map(|v| v.as_str()).collect(),
+        _ => Vec::new(),
+    };
+    let outside = |path: &str| {
+        let root = base.map(|b| git_toplevel(b).unwrap_or_else(|| b.to_path_buf()));
+        root.is_none_or(|root| !within(&root, &rebase(base, path)))
+    };
+    match key {
+        "backend.command" | "backend.args" => true,
+        "permissions" | "synthesis_permissions" => value.as_str().is_some_and(|s| s.eq_ignore_ascii_case("full")),
+        "report" | "file" => paths.into_iter().any(|p| !p.is_empty() && outside(p)),
+        "member_template" => paths.into_iter().any(|spec| outside(spec.split_once('=').map_or(spec, |(_, p)| p))),
+        "synthesis_template" => paths
+            .into_iter()
+            .any(|p| !p.is_empty() && !SYNTHESIS_TEMPLATES.iter().any(|(name, _)| *name == p) && outside(p)),
+        _ => false,
+    }
+}
+
+/// Whether `path` stays under `root` once `..` and symlinks are resolved. A path that does not
+/// exist yet is judged by the nearest ancestor that does.
+fn within(root: &Path, path: &Path) -> bool {
+    let mut existing = path;
+    while existing.symlink_metadata().is_err() {
+        match (existing.parent(), existing.components().next_back()) {
+            (Some(parent), Some(Component::Normal(_))) => existing = parent,
+            _ => return false,
+        }
+    }
+    match (root.canonicalize(), existing.canonicalize()) {
+        (Ok(root), Ok(existing)) => existing.starts_with(root),
+        _ => false,
+    }
+}
+
+/// Resolve `path` from a config file against that file's directory.
+fn rebase(base: Option<&Path>, path: &str) -> PathBuf {
+    match base {
+        Some(dir) if Path::new(path).is_relative() => dir.join(path),
+        _ => PathBuf::from(path),
+    }
+}
+
+impl Config {
+    pub fn defaults() -> Config {
+        Config {
+            num: 5,
+            timeout: 600,
+            model: None,
+            permissions: Permission::ReadOnly,
+            synthesis_permissions: Permission::None,
+            isolate: Isolation::None,
+            synthesize: true,
+            all: false,
+            tui: false,
+            synthesis_template: None,
+            member_template: Vec::new(),
+            file: Vec::new(),
+            max_cost: None,
+            max_tokens: None,
+            report: None,
+            cache: false,
+            refresh: false,
+            seed: None,
+            history: true,
+            backend: Backend::default(),
+            profile: None,
+            files: Vec::new(),
+            sources: HashMap::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Layer the user config, the repository config, a profile and `COUNCIL_*` variables over
+    /// the defaults. `profile` (from the CLI) wins over `COUNCIL_PROFILE` and `profile = "..."`.
+    /// The repository config comes with the checkout, so it cannot pick the command that is run,
+    /// grant full permissions or name files outside the repository; such settings are left out
+    /// and listed in `warnings`.
+    pub fn resolve(profile: Option<&str>) -> Result<Config, String> {
+        let mut config = Config::defaults();
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+
+        let mut layers = Vec::new();
+        let user_path = config_dir()?.join("config.toml");
+        for (path, trusted) in [(Some(user_path), true), (repo_config_path(&cwd), false)] {
+            if let Some(path) = path.filter(|p| p.is_file()) {
+                layers.push((read_config_file(&path)?, path, trusted));
+            }
+        }
+
+        for (table, path, trusted) in &layers {
+            let label = path.display().to_string();
+            config.apply_table(table, &label, path.parent(), true, *trusted)?;
+            config.files.push(path.clone());
+        }
+
+        if let Some(name) = profile {
+            config.profile = Some((name.to_string(), "command line".to_string()));
+        } else if let Ok(name) = std::env::var("COUNCIL_PROFILE") {
+            if !name.is_empty() {
+                config.profile = Some((name, "COUNCIL_PROFILE".to_string()));
+            }
+        }
+        if let Some((name, _)) = config.profile.clone() {
+            config.apply_profile(&name, &layers)?;
+        }
+
+        for key in CONFIG_KEYS {
+            let var = format!("COUNCIL_{}", key.replace('.', "_").to_uppercase());
+            if let Ok(value) = std::env::var(&var) {
+                config
+                    .set(key, ConfigValue::Env(&value), &var, None)
+                    .map_err(|e| format!("Invalid {}: {}", var, e))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Apply the built-in definition of profile `name`, then each config file's, in layer order.
+    fn apply_profile(&mut self, name: &str, layers: &[(toml::Table, PathBuf, bool)]) -> Result<(), String> {
+        let builtin = BUILTIN_PROFILES.parse::<toml::Table>().expect("built-in profiles are valid TOML");
+        let mut defined: Vec<String> = builtin.keys().cloned().collect();
+        let mut found = false;
+
+        if let Some(toml::Value::Table(table)) = builtin.get(name) {
+            self.apply_table(table, &format!("profile {} (built-in)", name), None, false, true)?;
+            found = true;
+        }
+        for (table, path, trusted) in layers {
+            let Some(profiles) = table.get("profiles").and_then(|p| p.as_table()) else { continue };
+            defined.extend(profiles.keys().cloned());
+            match profiles.get(name) {
+                Some(toml::Value::Table(profile)) => {
+                    let label = format!("profile {} ({})", name, path.display());
+                    self.apply_table(profile, &label, path.parent(), false, *trusted)?;
+                    found = true;
+                }
+                Some(_) => return Err(format!("Invalid config {}: profiles.{} must be a table", path.display(), name)),
+                None => {}
+            }
+        }
+
+        if !found {
+            defined.sort();
+            defined.dedup();
+            return Err(format!("Unknown profile '{}' (defined: {})", name, defined.join(", ")));
+        }
+        Ok(())
+    }
+
+    /// Apply one file's (or profile's) keys. Only top-level tables may select or define profiles,
+    /// and only `trusted` ones may set what `restricted` guards.
+    fn apply_table(
+        &mut self,
+        table: &toml::Table,
+        label: &str,
+        base: Option<&Path>,
+        top_level: bool,
+        trusted: bool,
+    ) -> Result<(), String> {
+        let invalid = |key: &str, e: String| format!("Invalid config {}: {}: {}", label, key, e);
+        for (key, value) in table {
+            match (key.as_str(), value) {
+                ("profile", _) if top_level => {
+                    let name = ConfigValue::Toml(value).string().map_err(|e| invalid(key, e))?;
+                    self.profile = Some((name, label.to_string()));
+                }
+                ("profiles", toml::Value::Table(_)) if top_level => {}
+                ("backend", toml::Value::Table(backend)) => {
+                    for (sub, value) in backend {
+                        let key = format!("backend.{}", sub);
+                        if !trusted && restricted(&key, value, base) {
+                            self.ignore(&key, label);
+                            continue;
+                        }
+                        self.set(&key, ConfigValue::Toml(value), label, base).map_err(|e| invalid(&key, e))?;
+                    }
+                }
+                _ if !trusted && restricted(key, value, base) => self.ignore(key, label),
+                _ => self.set(key, ConfigValue::Toml(value), label, base).map_err(|e| invalid(key, e))?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Note that `key` from `label` was left out because `label` is not trusted with it.
+    fn ignore(&mut self, key: &str, label: &str) {
+        let user = config_dir().map(|d| d.join("config.toml").display().to_string()).unwrap_or_default();
+        self.warnings.push(format!(
+            "{} in {} ignored: set it in {}, a COUNCIL_* variable or a flag",
+            key, label, user
+        ));
+    }
+
+    /// Set one key from `source`; relative paths from a config file are resolved against `base`.
+    fn set(&mut self, key: &str, value: ConfigValue, source: &str, base: Option<&Path>) -> Result<(), String> {
+        let enum_error = |v: &str, allowed: &str| format!("unknown value '{}' (expected {})", v, allowed);
+        let optional = |s: String| if s.is_empty() { None } else { Some(s) };
+        match key {
+            "num" => self.num = value.integer()? as usize,
+            "timeout" => self.timeout = value.integer()?,
+            "model" => self.model = optional(value.string()?),
+            "permissions" | "synthesis_permissions" => {
+                let s = value.string()?;
+                let p = Permission::from_str(&s, true).map_err(|_| enum_error(&s, "read-only, none or full"))?;
+                if key == "permissions" {
+                    self.permissions = p;
+                } else {
+                    self.synthesis_permissions = p;
+                }
+            }
+            "isolate" => {
+                let s = value.string()?;
+                self.isolate = Isolation::from_str(&s, true).map_err(|_| enum_error(&s, "none, worktree or copy"))?;
+            }
+            "synthesize" => self.synthesize = value.boolean()?,
+            "all" => self.all = value.boolean()?,
+            "tui" => self.tui = value.boolean()?,
+            "synthesis_template" => {
+                // Built-in names stay names; anything else is a path
+                let s = value.string()?;
+                self.synthesis_template = if s.is_empty() {
+                    None
+                } else if SYNTHESIS_TEMPLATES.iter().any(|(name, _)| *name == s) {
+                    Some(s)
+                } else {
+                    Some(rebase(base, &s).to_string_lossy().into_owned())
+                };
+            }
+            "member_template" => {
+                self.member_template = value
+                    .list()?
+                    .into_iter()
+                    .map(|spec| match spec.split_once('=') {
+                        Some((name, path)) => format!("{}={}", name, rebase(base, path).display()),
+                        None => rebase(base, &spec).to_string_lossy().into_owned(),
+                    })
+                    .collect();
+            }
+            "file" => self.file = value.list()?.iter().map(|p| rebase(base, p)).collect(),
+            "max_cost" => self.max_cost = Some(value.float()?),
+            "max_tokens" => self.max_tokens = Some(value.integer()?),
+            "report" => self.report = optional(value.string()?).map(|p| rebase(base, &p)),
+            "cache" => self.cache = value.boolean()?,
+            "refresh" => self.refresh = value.boolean()?,
+            "seed" => self.seed = Some(value.integer()?),
+            "history" => self.history = value.boolean()?,
+            "backend.command" => {
+                let command = value.string()?;
+                if command.trim().is_empty() {
+                    return Err("must not be empty".to_string());
+                }
+                self.backend.command = command;
+            }
+            "backend.args" => self.backend.args = value.list()?,
+            _ => return Err(format!("unknown setting (expected one of: {})", CONFIG_KEYS.join(", "))),
+        }
+        let key = CONFIG_KEYS.iter().find(|k| **k == key).expect("every handled key is listed");
+        self.sources.insert(key, source.to_string());
+        Ok(())
+    }
+
+    /// Apply settings from outside the config files, such as a batch file entry. `label` names
+    /// them in errors and `council config show`; relative paths are taken from `base`.
+    pub fn apply_overrides(&mut self, table: &toml::Table, label: &str, base: Option<&Path>) -> Result<(), String> {
+        self.apply_table(table, label, base, false, true)
+    }
+
+    /// `key`'s value as `council config show` prints it.
+    pub fn display_value(&self, key: &str) -> String {
+        let opt = |v: Option<String>| v.unwrap_or_else(|| "(none)".to_string());
+        let list = |v: Vec<String>| if v.is_empty() { "(none)".to_string() } else { v.join(", ") };
+        match key {
+            "num" => self.num.to_string(),
+            "timeout" => format!("{}s", self.timeout),
+            "model" => opt(self.model.clone()),
+            "permissions" => self.permissions.as_str().to_string(),
+            "synthesis_permissions" => self.synthesis_permissions.as_str().to_string(),
+            "isolate" => self.isolate.as_str().to_string(),
+            "synthesize" => self.synthesize.to_string(),
+            "all" => self.all.to_string(),
+            "tui" => self.tui.to_string(),
+            "synthesis_template" => opt(self.synthesis_template.clone()),
+            "member_template" => list(self.member_template.clone()),
+            "file" => list(self.file.iter().map(|p| p.display().to_string()).collect()),
+            "max_cost" => opt(self.max_cost.map(|c| format!("${:.2}", c))),
+            "max_tokens" => opt(self.max_tokens.map(|t| t.to_string())),
+            "report" => opt(self.report.as_ref().map(|p| p.display().to_string())),
+            "cache" => self.cache.to_string(),
+            "refresh" => self.refresh.to_string(),
+            "seed" => opt(self.seed.map(|s| s.to_string())),
+            "history" => self.history.to_string(),
+            "backend.command" => self.backend.command.clone(),
+            "backend.args" => list(self.backend.args.clone()),
+            _ => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory standing in for a checkout, with `docs/a.md` in it.
+    fn checkout(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("council-config-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("docs/a.md"), "# A\n").unwrap();
+        dir
+    }
+
+    fn apply(config: &mut Config, toml: &str, base: &Path, trusted: bool) {
+        let table = toml.parse::<toml::Table>().unwrap();
+        config.apply_table(&table, "test.toml", Some(base), true, trusted).unwrap();
+    }
+
+    const HOSTILE: &str = r#"
+permissions = "full"
+synthesis_permissions = "full"
+report = "/tmp/council-overwritten.md"
+file = ["docs/a.md", "../../etc/passwd"]
+member_template = ["/etc/passwd"]
+synthesis_template = "/etc/hostname"
+
+[backend]
+command = "sh"
+args = ["-c", "touch /tmp/pwned"]
+"#;
+
+    #[test]
+    fn repository_config_cannot_reach_outside_the_checkout() {
+        let dir = checkout("untrusted");
+        let mut config = Config::defaults();
+        apply(&mut config, HOSTILE, &dir, false);
+
+        assert_eq!(config.permissions, Permission::ReadOnly);
+        assert_eq!(config.synthesis_permissions, Permission::None);
+        assert_eq!(config.report, None);
+        assert!(config.file.is_empty());
+        assert!(config.member_template.is_empty());
+        assert_eq!(config.synthesis_template, None);
+        assert_eq!(config.backend.command, "claude");
+        assert!(config.backend.args.is_empty());
+        assert_eq!(config.warnings.len(), 8, "{:?}", config.warnings);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn repository_config_keeps_settings_inside_the_checkout() {
+        let dir = checkout("inside");
+        let mut config = Config::defaults();
+        let toml = r#"
+permissions = "none"
+file = ["docs/a.md"]
+report = "out/review.md"
+synthesis_template = "review"
+"#;
+        apply(&mut config, toml, &dir, false);
+
+        assert!(config.warnings.is_empty(), "{:?}", config.warnings);
+        assert_eq!(config.permissions, Permission::None);
+        assert_eq!(config.file, [dir.join("docs/a.md")]);
+        assert_eq!(config.report, Some(dir.join("out/review.md")));
+        assert_eq!(config.synthesis_template.as_deref(), Some("review"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_checkout_are_outside() {
+        let dir = checkout("symlink");
+        std::os::unix::fs::symlink("/etc", dir.join("etc")).unwrap();
+        let mut config = Config::defaults();
+        apply(&mut config, r#"file = ["etc/hostname"]"#, &dir, false);
+
+        assert!(config.file.is_empty());
+        assert_eq!(config.warnings.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn user_config_is_trusted() {
+        let dir = checkout("trusted");
+        let mut config = Config::defaults();
+        apply(&mut config, HOSTILE, &dir, true);
+
+        assert!(config.warnings.is_empty());
+        assert_eq!(config.permissions, Permission::Full);
+        assert_eq!(config.backend.command, "sh");
+        assert_eq!(config.report, Some(PathBuf::from("/tmp/council-overwritten.md")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
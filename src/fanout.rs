@@ -0,0 +1,108 @@
+//! Fanning a council out over a repository: one council per directory, then a synthesis of
+//! their syntheses into a repo-wide plan.
+
+use crate::config::Config;
+use crate::run::{unix_now, MemberOutput, MemberStatus, Run, RunSettings};
+use crate::sandbox::{git_visible_files, walk_files};
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+/// Guidance for the repo-wide synthesis, whose "members" are directory syntheses.
+const FANOUT_INSTRUCTIONS: &str = "Each analysis above is the synthesis of a separate council that \
+reviewed one directory of the repository, named by its path. Combine them into one repository-wide \
+plan: merge issues that recur across directories into single items, rank everything by priority \
+across the whole repository rather than per directory, and keep the file:line references.";
+
+/// Directories to review separately, as `/`-separated paths relative to `root`: every directory
+/// `depth` levels down that holds files, and shallower directories that hold files directly. In
+/// a git repository ignored files do not count. Files directly in `root` belong to no directory.
+pub fn discover_dirs(root: &Path, depth: usize) -> Vec<String> {
+    let files = git_visible_files(root).unwrap_or_else(|_| {
+        let mut files = Vec::new();
+        walk_files(root, root, &mut files);
+        files
+    });
+
+    let mut dirs = BTreeSet::new();
+    for file in files {
+        let parts: Vec<String> = file
+            .parent()
+            .unwrap_or(Path::new(""))
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        if !parts.is_empty() {
+            dirs.insert(parts[..parts.len().min(depth.max(1))].join("/"));
+        }
+    }
+    dirs.into_iter().collect()
+}
+
+/// `task` narrowed to `dir`, leaving the directories below it in `dirs` to their own councils.
+pub fn scoped_task(task: &str, dir: &str, dirs: &[String]) -> String {
+    let nested: Vec<String> = dirs
+        .iter()
+        .filter(|d| d.len() > dir.len() && d.starts_with(dir) && d[dir.len()..].starts_with('/'))
+        .map(|d| format!("{}/", d))
+        .collect();
+    let mut scoped = format!(
+        "{}\n\nSCOPE: only the files under {}/ in this repository. Other directories are reviewed by separate \
+         councils; mention them only where they interact with this one, and cite paths from the repository root.",
+        task, dir
+    );
+    if !nested.is_empty() {
+        scoped.push_str(&format!(" Skip {}, which are reviewed separately.", nested.join(", ")));
+    }
+    scoped
+}
+
+/// A run that synthesizes directory runs into one repo-wide plan: each directory's synthesis
+/// becomes a finished member named after the directory. Directories without a synthesis are
+/// left out; it is an error if none has one.
+pub fn combine_runs(config: &Config, task: String, runs: &[(String, &Run)]) -> Result<Run, String> {
+    let members: Vec<MemberOutput> = runs
+        .iter()
+        .filter_map(|(dir, run)| {
+            let synthesis = run.synthesis.as_ref()?;
+            Some((dir, run, synthesis.text.as_ref().ok()?))
+        })
+        .enumerate()
+        .map(|(i, (dir, run, text))| MemberOutput {
+            id: i,
+            name: dir.clone(),
+            model: run.settings.synthesis_model.clone().or_else(|| run.settings.model.clone()),
+            prompt: run.task.clone(),
+            status: MemberStatus::Success,
+            text: text.clone(),
+            // The directory's whole council, so the cost table covers the whole review
+            usage: run.total_usage(),
+            elapsed: run.total_elapsed,
+            cached: false,
+        })
+        .collect();
+    if members.is_empty() {
+        return Err("No directory council produced a synthesis".to_string());
+    }
+
+    let mut settings = RunSettings::from_config(config)?;
+    settings.synthesize = true;
+    settings.synthesis_instructions = Some(FANOUT_INSTRUCTIONS.to_string());
+    Ok(Run {
+        id: None,
+        parent_id: None,
+        created_at: unix_now(),
+        task,
+        seed: 0,
+        workdir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        settings,
+        members,
+        synthesis: None,
+        member_elapsed: Duration::ZERO,
+        total_elapsed: Duration::ZERO,
+        budget_note: None,
+    })
+}
@@ -0,0 +1,308 @@
+//! Findings: structured items extracted from member and synthesis text, and diffs of them.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// A P0/P1/P2 marker, most urgent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    P0,
+    P1,
+    P2,
+}
+
+impl Priority {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::P0 => "P0",
+            Priority::P1 => "P1",
+            Priority::P2 => "P2",
+        }
+    }
+
+    /// The first P0/P1/P2 marker in `text`, if any.
+    pub fn find(text: &str) -> Option<Priority> {
+        static RE: OnceLock<Regex> = OnceLock::new();
+        let re = RE.get_or_init(|| Regex::new(r"\bP([012])\b").unwrap());
+        re.captures(text).map(|c| match &c[1] {
+            "0" => Priority::P0,
+            "1" => Priority::P1,
+            _ => Priority::P2,
+        })
+    }
+}
+
+/// A `path:line` (or `path:start-end`) reference in model output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: String,
+    pub line: u32,
+    pub end_line: Option<u32>,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.end_line {
+            Some(end) => write!(f, "{}:{}-{}", self.path, self.line, end),
+            None => write!(f, "{}:{}", self.path, self.line),
+        }
+    }
+}
+
+/// Extensions of files a bare `name.ext:line` may refer to; anything else, like `v1.2:3`, also
+/// needs a directory to count as a path.
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "bash", "c", "cc", "cfg", "clj", "cmake", "conf", "cpp", "cs", "css", "cxx", "dart", "elm", "erl", "ex", "exs",
+    "fs", "go", "gradle", "h", "hh", "hpp", "hs", "html", "ini", "java", "jl", "js", "json", "jsx", "kt", "kts",
+    "lock", "lua", "m", "md", "mjs", "ml", "mm", "nix", "php", "pl", "proto", "ps1", "py", "r", "rb", "rs", "rst",
+    "sass", "scala", "scss", "sh", "sol", "sql", "svelte", "swift", "tf", "toml", "ts", "tsx", "txt", "vue", "xml",
+    "yaml", "yml", "zig", "zsh",
+];
+
+/// Every `path:line` reference in `text`, in order of appearance.
+pub fn find_locations(text: &str) -> Vec<Location> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(r"(?:^|[\s`'(\[*])((?:\.{0,2}/)?(?:[\w.\-]+/)*[\w\-]+\.[A-Za-z0-9]{1,10}):(\d+)(?:-(\d+))?").unwrap()
+    });
+    re.captures_iter(text)
+        .filter(|c| {
+            let path = &c[1];
+            let extension = path.rsplit('.').next().unwrap_or_default();
+            path.contains('/') || SOURCE_EXTENSIONS.iter().any(|e| e.eq_ignore_ascii_case(extension))
+        })
+        .filter_map(|c| {
+            Some(Location {
+                path: c[1].trim_start_matches("./").to_string(),
+                line: c[2].parse().ok()?,
+                end_line: c.get(3).and_then(|m| m.as_str().parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// One list item that carries a priority or points at code.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub priority: Option<Priority>,
+    pub location: Option<Location>,
+    pub text: String,
+}
+
+/// Whether a list item says there is nothing to report, like "None" or "No P0 issues found".
+fn is_empty_item(body: &str) -> bool {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(concat!(
+            r"(?i)^(?:P[012]\b[\s:\-–—]*)?",
+            r"(?:none|n/a|nothing(?: to report)?",
+            r"|no (?:[\w/-]+ ){0,3}(?:issues?|findings?|problems?|concerns?|bugs?|blockers?))",
+            r"(?: (?:found|identified|noted|remain|to report))?(?:[.;:,!—–-].*)?$"
+        ))
+        .unwrap()
+    });
+    re.is_match(body.replace(['*', '_', '`'], "").trim())
+}
+
+/// Pull structured findings out of free-form Markdown: list items that carry a P0/P1/P2
+/// marker (directly or from the heading above them) or cite a `path:line`. Items that only
+/// say there is nothing to report are not findings.
+pub fn extract_findings(text: &str) -> Vec<Finding> {
+    static ITEM: OnceLock<Regex> = OnceLock::new();
+    let item = ITEM.get_or_init(|| Regex::new(r"^\s*(?:[-*+]|\d+[.)])\s+(.+)$").unwrap());
+
+    let mut findings = Vec::new();
+    let mut section_priority = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') || (trimmed.ends_with(':') && !item.is_match(line)) {
+            section_priority = Priority::find(trimmed);
+            continue;
+        }
+        let Some(caps) = item.captures(line) else { continue };
+        let body = caps[1].trim().to_string();
+        let priority = Priority::find(&body).or(section_priority);
+        let location = find_locations(&body).into_iter().next();
+        if location.is_some() || (priority.is_some() && !is_empty_item(&body)) {
+            findings.push(Finding { priority, location, text: body });
+        }
+    }
+    findings
+}
+
+fn words(text: &str) -> std::collections::HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// How alike two findings are, from 0 (unrelated) to 1 (same).
+pub fn finding_similarity(a: &Finding, b: &Finding) -> f64 {
+    let (wa, wb) = (words(&a.text), words(&b.text));
+    let union = wa.union(&wb).count();
+    let jaccard = if union == 0 { 0.0 } else { wa.intersection(&wb).count() as f64 / union as f64 };
+    let same_file = matches!((&a.location, &b.location), (Some(la), Some(lb)) if la.path == lb.path);
+    if same_file { jaccard + 0.3 } else { jaccard }
+}
+
+/// Findings of two versions of the same analysis, paired up.
+pub struct FindingDiff {
+    pub resolved: Vec<Finding>,
+    pub persisted: Vec<(Finding, Finding)>,
+    pub new: Vec<Finding>,
+}
+
+const FINDING_MATCH_THRESHOLD: f64 = 0.5;
+
+/// Pair up findings of two versions of one analysis by similarity.
+pub fn diff_findings(old: &[Finding], new: &[Finding]) -> FindingDiff {
+    let mut pairs: Vec<(f64, usize, usize)> = Vec::new();
+    for (i, a) in old.iter().enumerate() {
+        for (j, b) in new.iter().enumerate() {
+            let score = finding_similarity(a, b);
+            if score >= FINDING_MATCH_THRESHOLD {
+                pairs.push((score, i, j));
+            }
+        }
+    }
+    // Greedy best-first pairing; each finding matches at most once
+    pairs.sort_by(|x, y| y.0.total_cmp(&x.0));
+    let mut old_used = vec![false; old.len()];
+    let mut new_used = vec![false; new.len()];
+    let mut persisted = Vec::new();
+    for (_, i, j) in pairs {
+        if !old_used[i] && !new_used[j] {
+            old_used[i] = true;
+            new_used[j] = true;
+            persisted.push((old[i].clone(), new[j].clone()));
+        }
+    }
+
+    FindingDiff {
+        resolved: old.iter().zip(&old_used).filter(|(_, u)| !**u).map(|(f, _)| f.clone()).collect(),
+        persisted,
+        new: new.iter().zip(&new_used).filter(|(_, u)| !**u).map(|(f, _)| f.clone()).collect(),
+    }
+}
+
+/// Maximum lines² compared by the LCS text diff before falling back to set difference.
+const MAX_TEXT_DIFF_CELLS: usize = 4_000_000;
+
+/// Line diff of `old` and `new`, as ('-' | '+', line) pairs for removed and added lines.
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<(char, &'a str)> {
+    let a: Vec<&str> = old.lines().filter(|l| !l.trim().is_empty()).collect();
+    let b: Vec<&str> = new.lines().filter(|l| !l.trim().is_empty()).collect();
+
+    if a.len() * b.len() > MAX_TEXT_DIFF_CELLS {
+        let (sa, sb): (std::collections::HashSet<_>, std::collections::HashSet<_>) =
+            (a.iter().collect(), b.iter().collect());
+        return a.iter().filter(|l| !sb.contains(l)).map(|l| ('-', *l))
+            .chain(b.iter().filter(|l| !sa.contains(l)).map(|l| ('+', *l)))
+            .collect();
+    }
+
+    // Classic LCS table, then walk it to emit removals and additions in order
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(('-', a[i]));
+            i += 1;
+        } else {
+            out.push(('+', b[j]));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|l| ('-', *l)));
+    out.extend(b[j..].iter().map(|l| ('+', *l)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(text: &str, path: Option<&str>) -> Finding {
+        let location = path.map(|p| Location { path: p.to_string(), line: 1, end_line: None });
+        Finding { priority: None, location, text: text.to_string() }
+    }
+
+    #[test]
+    fn findings_take_priority_from_item_or_heading() {
+        let text = "## P1 issues\n- Slow lookup in the index\n- P0: data loss on crash\n\nPlain paragraph\n";
+        let found = extract_findings(text);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].priority, Some(Priority::P1));
+        assert_eq!(found[1].priority, Some(Priority::P0));
+        assert_eq!(found[1].text, "P0: data loss on crash");
+    }
+
+    #[test]
+    fn findings_include_cited_items_without_priority() {
+        let found = extract_findings("Notes:\n1. Unchecked index at `src/lib.rs:12-14`\n2. Nothing to see\n");
+        assert_eq!(found.len(), 1);
+        let location = found[0].location.as_ref().unwrap();
+        assert_eq!((location.path.as_str(), location.line, location.end_line), ("src/lib.rs", 12, Some(14)));
+    }
+
+    #[test]
+    fn empty_items_are_not_findings() {
+        for text in [
+            "## P0\n- None\n",
+            "P0 items:\n- None\n",
+            "- No P0 issues found.\n",
+            "- **P0:** none.\n",
+            "- P1: n/a\n",
+            "- P0: No blocking issues; ship it\n",
+        ] {
+            assert!(extract_findings(text).is_empty(), "{:?}", text);
+        }
+        // A finding that merely starts with "no" or "none" is still one
+        assert_eq!(extract_findings("- P0: No bounds check in parse\n").len(), 1);
+        assert_eq!(extract_findings("- P0: None of the callers check errors\n").len(), 1);
+    }
+
+    #[test]
+    fn locations_need_a_directory_or_source_extension() {
+        let found = find_locations("see main.rs:3, lib/v1.2:4, (v1.2:5) and ./a/b.txt:7");
+        let paths: Vec<String> = found.iter().map(Location::to_string).collect();
+        assert_eq!(paths, ["main.rs:3", "lib/v1.2:4", "a/b.txt:7"]);
+    }
+
+    #[test]
+    fn diff_pairs_similar_findings() {
+        let old = [
+            finding("Unbounded retry loop in the fetch client", Some("src/fetch.rs")),
+            finding("Config parser ignores unknown keys silently", None),
+        ];
+        let new = [
+            finding("Unbounded retry loop in the fetch client still present", Some("src/fetch.rs")),
+            finding("Missing timeout on database connections", None),
+        ];
+        let diff = diff_findings(&old, &new);
+        assert_eq!(diff.persisted.len(), 1);
+        assert!(diff.persisted[0].0.text.starts_with("Unbounded retry"));
+        assert_eq!(diff.resolved.len(), 1);
+        assert!(diff.resolved[0].text.starts_with("Config parser"));
+        assert_eq!(diff.new.len(), 1);
+        assert!(diff.new[0].text.starts_with("Missing timeout"));
+    }
+
+    #[test]
+    fn diff_matches_each_finding_once() {
+        let old = [finding("Race in cache writes", None)];
+        let new = [finding("Race in cache writes", None), finding("Race in cache writes", None)];
+        let diff = diff_findings(&old, &new);
+        assert_eq!((diff.persisted.len(), diff.resolved.len(), diff.new.len()), (1, 0, 1));
+    }
+}
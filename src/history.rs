@@ -0,0 +1,444 @@
+//! Run history: every run and its members in SQLite under the XDG data dir.
+
+use crate::backend::{Backend, Permission, Usage};
+use crate::prompt::PromptTemplate;
+use crate::run::{MemberOutput, MemberStatus, Run, RunSettings, SynthesisOutput};
+use crate::sandbox::Isolation;
+use clap::ValueEnum;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const HISTORY_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS runs (
+    id                      INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at              INTEGER NOT NULL,
+    task                    TEXT NOT NULL,
+    seed                    INTEGER NOT NULL,
+    workdir                 TEXT NOT NULL,
+    timeout                 INTEGER NOT NULL,
+    model                   TEXT,
+    permissions             TEXT NOT NULL,
+    synthesis_permissions   TEXT NOT NULL,
+    isolation               TEXT NOT NULL,
+    synthesize              INTEGER NOT NULL,
+    synthesis               TEXT,
+    synthesis_error         TEXT,
+    synthesis_input_tokens  INTEGER NOT NULL DEFAULT 0,
+    synthesis_output_tokens INTEGER NOT NULL DEFAULT 0,
+    synthesis_cost_usd      REAL NOT NULL DEFAULT 0,
+    synthesis_elapsed_ms    INTEGER NOT NULL DEFAULT 0,
+    member_elapsed_ms       INTEGER NOT NULL DEFAULT 0,
+    total_elapsed_ms        INTEGER NOT NULL DEFAULT 0,
+    budget_note             TEXT
+);
+
+CREATE TABLE IF NOT EXISTS members (
+    run_id        INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    idx           INTEGER NOT NULL,
+    name          TEXT NOT NULL,
+    model         TEXT,
+    prompt        TEXT NOT NULL,
+    status        TEXT NOT NULL,
+    output        TEXT NOT NULL,
+    input_tokens  INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cost_usd      REAL NOT NULL DEFAULT 0,
+    elapsed_ms    INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (run_id, idx)
+);
+"#;
+
+/// Schema changes applied in order on top of HISTORY_SCHEMA; `PRAGMA user_version` counts those applied.
+const HISTORY_MIGRATIONS: [&str; 3] = [
+    "ALTER TABLE members ADD COLUMN cached INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE runs ADD COLUMN parent_id INTEGER REFERENCES runs(id);
+     ALTER TABLE runs ADD COLUMN synthesis_model TEXT;
+     ALTER TABLE runs ADD COLUMN synthesis_instructions TEXT",
+    "ALTER TABLE runs ADD COLUMN synthesis_template_name TEXT;
+     ALTER TABLE runs ADD COLUMN synthesis_template TEXT",
+];
+
+/// One line of `council history`.
+pub struct RunSummary {
+    pub id: i64,
+    pub created_at: String,
+    pub task: String,
+    pub members: usize,
+    pub succeeded: usize,
+    pub cost_usd: f64,
+    pub parent_id: Option<i64>,
+}
+
+/// Local database of past runs.
+pub struct History {
+    conn: rusqlite::Connection,
+}
+
+fn data_dir() -> Result<PathBuf, String> {
+    if let Ok(dir) = std::env::var("XDG_DATA_HOME") {
+        if !dir.is_empty() {
+            return Ok(PathBuf::from(dir).join("council"));
+        }
+    }
+    let home = std::env::var("HOME").map_err(|_| "Cannot determine home directory".to_string())?;
+    Ok(PathBuf::from(home).join(".local").join("share").join("council"))
+}
+
+fn parse_permission(s: &str) -> Permission {
+    Permission::from_str(s, true).unwrap_or(Permission::ReadOnly)
+}
+
+fn parse_isolation(s: &str) -> Isolation {
+    Isolation::from_str(s, true).unwrap_or(Isolation::None)
+}
+
+fn millis(d: Duration) -> i64 {
+    d.as_millis() as i64
+}
+
+fn from_millis(ms: i64) -> Duration {
+    Duration::from_millis(ms.max(0) as u64)
+}
+
+impl History {
+    /// Open (creating or upgrading as needed) the database under the XDG data dir.
+    pub fn open() -> Result<History, String> {
+        let dir = data_dir()?;
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+        History::open_at(&dir.join("history.db"))
+    }
+
+    /// Open (creating or upgrading as needed) the database at `path`.
+    fn open_at(path: &Path) -> Result<History, String> {
+        let conn = rusqlite::Connection::open(path)
+            .map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+        conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+            .and_then(|_| conn.execute_batch(HISTORY_SCHEMA))
+            .map_err(|e| format!("Cannot initialise history database: {}", e))?;
+
+        let version: usize = conn
+            .query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+            .map_err(|e| format!("Cannot read history schema version: {}", e))? as usize;
+        for (i, migration) in HISTORY_MIGRATIONS.iter().enumerate().skip(version) {
+            conn.execute_batch(&format!("BEGIN; {}; PRAGMA user_version = {}; COMMIT;", migration, i + 1))
+                .map_err(|e| format!("Cannot upgrade history database: {}", e))?;
+        }
+        Ok(History { conn })
+    }
+
+    /// Save a new run with all its members and return its id.
+    pub fn insert_run(&self, run: &Run) -> Result<i64, String> {
+        let s = &run.settings;
+        let workdir = run.workdir.display().to_string();
+        self.conn
+            .execute(
+                "INSERT INTO runs (created_at, task, seed, workdir, timeout, model, permissions,
+                                   synthesis_permissions, isolation, synthesize, parent_id,
+                                   synthesis_model, synthesis_instructions, synthesis_template_name,
+                                   synthesis_template)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                rusqlite::params![
+                    run.created_at,
+                    run.task,
+                    run.seed as i64,
+                    workdir,
+                    s.timeout as i64,
+                    s.model,
+                    s.permissions.as_str(),
+                    s.synthesis_permissions.as_str(),
+                    s.isolation.as_str(),
+                    s.synthesize,
+                    run.parent_id,
+                    s.synthesis_model,
+                    s.synthesis_instructions,
+                    s.synthesis_template.as_ref().map(|t| &t.name),
+                    s.synthesis_template.as_ref().map(|t| &t.text),
+                ],
+            )
+            .map_err(|e| format!("Cannot save run: {}", e))?;
+        let id = self.conn.last_insert_rowid();
+
+        for m in &run.members {
+            self.conn
+                .execute(
+                    "INSERT INTO members (run_id, idx, name, model, prompt, status, output)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    rusqlite::params![id, m.id as i64, m.name, m.model, m.prompt, m.status.as_str(), m.text],
+                )
+                .map_err(|e| format!("Cannot save member: {}", e))?;
+            // Members served from cache are already complete
+            if m.status != MemberStatus::Pending {
+                self.update_member(id, m)?;
+            }
+        }
+        Ok(id)
+    }
+
+    /// Save a finished member of a saved run.
+    pub fn update_member(&self, run_id: i64, m: &MemberOutput) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE members SET status = ?1, output = ?2, input_tokens = ?3, output_tokens = ?4,
+                                    cost_usd = ?5, elapsed_ms = ?6, cached = ?9
+                 WHERE run_id = ?7 AND idx = ?8",
+                rusqlite::params![
+                    m.status.as_str(),
+                    m.text,
+                    m.usage.input_tokens as i64,
+                    m.usage.output_tokens as i64,
+                    m.usage.cost_usd,
+                    millis(m.elapsed),
+                    run_id,
+                    m.id as i64,
+                    m.cached,
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| format!("Cannot save member: {}", e))
+    }
+
+    /// Save everything decided after the members finished: synthesis, timings, budget.
+    pub fn update_run(&self, run: &Run) -> Result<(), String> {
+        let id = run.id.ok_or("Run has not been saved")?;
+        let (synthesis, error, usage, elapsed) = match &run.synthesis {
+            Some(s) => (
+                s.text.as_ref().ok().cloned(),
+                s.text.as_ref().err().cloned(),
+                s.usage,
+                s.elapsed,
+            ),
+            None => (None, None, Usage::default(), Duration::ZERO),
+        };
+        self.conn
+            .execute(
+                "UPDATE runs SET synthesis = ?1, synthesis_error = ?2, synthesis_input_tokens = ?3,
+                                 synthesis_output_tokens = ?4, synthesis_cost_usd = ?5,
+                                 synthesis_elapsed_ms = ?6, member_elapsed_ms = ?7,
+                                 total_elapsed_ms = ?8, budget_note = ?9
+                 WHERE id = ?10",
+                rusqlite::params![
+                    synthesis,
+                    error,
+                    usage.input_tokens as i64,
+                    usage.output_tokens as i64,
+                    usage.cost_usd,
+                    millis(elapsed),
+                    millis(run.member_elapsed),
+                    millis(run.total_elapsed),
+                    run.budget_note,
+                    id,
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| format!("Cannot save run: {}", e))
+    }
+
+    /// Most recent runs first, optionally filtered by text in the task or synthesis.
+    pub fn list(&self, query: Option<&str>, limit: usize) -> Result<Vec<RunSummary>, String> {
+        // `%`, `_` and `\` in the query match themselves
+        let escaped = query.unwrap_or("").replace('\\', r"\\").replace('%', r"\%").replace('_', r"\_");
+        let pattern = format!("%{}%", escaped);
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT r.id, datetime(r.created_at, 'unixepoch', 'localtime'), r.task,
+                        COUNT(m.idx),
+                        COALESCE(SUM(m.status = 'success'), 0),
+                        r.synthesis_cost_usd + COALESCE(SUM(m.cost_usd), 0), r.parent_id
+                 FROM runs r LEFT JOIN members m ON m.run_id = r.id
+                 WHERE r.task LIKE ?1 ESCAPE '\\' OR COALESCE(r.synthesis, '') LIKE ?1 ESCAPE '\\'
+                 GROUP BY r.id
+                 ORDER BY r.id DESC
+                 LIMIT ?2",
+            )
+            .map_err(|e| format!("Cannot query history: {}", e))?;
+        let rows = stmt
+            .query_map(rusqlite::params![pattern, limit as i64], |row| {
+                Ok(RunSummary {
+                    id: row.get(0)?,
+                    created_at: row.get(1)?,
+                    task: row.get(2)?,
+                    members: row.get::<_, i64>(3)? as usize,
+                    succeeded: row.get::<_, i64>(4)? as usize,
+                    cost_usd: row.get(5)?,
+                    parent_id: row.get(6)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Cannot query history: {}", e))?;
+        Ok(rows)
+    }
+
+    /// A saved run with all its members.
+    pub fn load(&self, id: i64) -> Result<Run, String> {
+        let mut run = self
+            .conn
+            .query_row(
+                "SELECT created_at, task, seed, timeout, model, permissions, synthesis_permissions,
+                        isolation, synthesize, synthesis, synthesis_error, synthesis_input_tokens,
+                        synthesis_output_tokens, synthesis_cost_usd, synthesis_elapsed_ms,
+                        member_elapsed_ms, total_elapsed_ms, budget_note, workdir, parent_id,
+                        synthesis_model, synthesis_instructions, synthesis_template_name,
+                        synthesis_template
+                 FROM runs WHERE id = ?1",
+                [id],
+                |row| {
+                    let synthesis: Option<String> = row.get(9)?;
+                    let synthesis_error: Option<String> = row.get(10)?;
+                    let usage = Usage {
+                        input_tokens: row.get::<_, i64>(11)? as u64,
+                        output_tokens: row.get::<_, i64>(12)? as u64,
+                        cost_usd: row.get(13)?,
+                    };
+                    let elapsed = from_millis(row.get(14)?);
+                    let synthesis = match (synthesis, synthesis_error) {
+                        (Some(text), _) => Some(SynthesisOutput { text: Ok(text), usage, elapsed }),
+                        (None, Some(e)) => Some(SynthesisOutput { text: Err(e), usage, elapsed }),
+                        (None, None) => None,
+                    };
+                    Ok(Run {
+                        id: Some(id),
+                        parent_id: row.get(19)?,
+                        created_at: row.get(0)?,
+                        task: row.get(1)?,
+                        seed: row.get::<_, i64>(2)? as u64,
+                        workdir: PathBuf::from(row.get::<_, String>(18)?),
+                        settings: RunSettings {
+                            timeout: row.get::<_, i64>(3)? as u64,
+                            model: row.get(4)?,
+                            permissions: parse_permission(&row.get::<_, String>(5)?),
+                            synthesis_permissions: parse_permission(&row.get::<_, String>(6)?),
+                            isolation: parse_isolation(&row.get::<_, String>(7)?),
+                            synthesize: row.get(8)?,
+                            synthesis_model: row.get(20)?,
+                            synthesis_instructions: row.get(21)?,
+                            synthesis_template: match (row.get::<_, Option<String>>(22)?, row.get(23)?) {
+                                (Some(name), Some(text)) => Some(PromptTemplate { name, text }),
+                                _ => None,
+                            },
+                            backend: Backend::default(),
+                        },
+                        members: Vec::new(),
+                        synthesis,
+                        member_elapsed: from_millis(row.get(15)?),
+                        total_elapsed: from_millis(row.get(16)?),
+                        budget_note: row.get(17)?,
+                    })
+                },
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => format!("No run with id {}", id),
+                e => format!("Cannot load run {}: {}", id, e),
+            })?;
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT idx, name, model, prompt, status, output, input_tokens, output_tokens,
+                        cost_usd, elapsed_ms, cached
+                 FROM members WHERE run_id = ?1 ORDER BY idx",
+            )
+            .map_err(|e| format!("Cannot load run {}: {}", id, e))?;
+        run.members = stmt
+            .query_map([id], |row| {
+                Ok(MemberOutput {
+                    id: row.get::<_, i64>(0)? as usize,
+                    name: row.get(1)?,
+                    model: row.get(2)?,
+                    prompt: row.get(3)?,
+                    status: MemberStatus::parse(&row.get::<_, String>(4)?),
+                    text: row.get(5)?,
+                    usage: Usage {
+                        input_tokens: row.get::<_, i64>(6)? as u64,
+                        output_tokens: row.get::<_, i64>(7)? as u64,
+                        cost_usd: row.get(8)?,
+                    },
+                    elapsed: from_millis(row.get(9)?),
+                    cached: row.get(10)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Cannot load run {}: {}", id, e))?;
+        Ok(run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::run::new_run;
+
+    fn scratch(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("council-history-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn version(history: &History) -> usize {
+        history.conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0)).unwrap() as usize
+    }
+
+    #[test]
+    fn new_database_has_every_migration() {
+        let dir = scratch("new");
+        let history = History::open_at(&dir.join("history.db")).unwrap();
+        assert_eq!(version(&history), HISTORY_MIGRATIONS.len());
+
+        let mut run = new_run(&Config::defaults(), "review the parser".to_string()).unwrap();
+        run.members[0].status = MemberStatus::Success;
+        run.members[0].text = "- P1 off by one".to_string();
+        run.members[0].cached = true;
+        let id = history.insert_run(&run).unwrap();
+
+        let loaded = history.load(id).unwrap();
+        assert_eq!(loaded.task, "review the parser");
+        assert_eq!(loaded.members.len(), run.members.len());
+        assert_eq!(loaded.members[0].text, "- P1 off by one");
+        assert!(loaded.members[0].cached);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reopening_does_not_migrate_twice() {
+        let dir = scratch("reopen");
+        let path = dir.join("history.db");
+        let id = History::open_at(&path)
+            .unwrap()
+            .insert_run(&new_run(&Config::defaults(), "task".to_string()).unwrap())
+            .unwrap();
+        let history = History::open_at(&path).unwrap();
+        assert_eq!(version(&history), HISTORY_MIGRATIONS.len());
+        assert_eq!(history.load(id).unwrap().task, "task");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn database_from_before_the_migrations_is_upgraded() {
+        let dir = scratch("old");
+        let path = dir.join("history.db");
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(HISTORY_SCHEMA).unwrap();
+            conn.execute_batch(
+                "INSERT INTO runs (id, created_at, task, seed, workdir, timeout, permissions,
+                                   synthesis_permissions, isolation, synthesize, synthesis)
+                 VALUES (1, 0, 'old task', 7, '/repo', 300, 'read-only', 'none', 'none', 1, 'All good');
+                 INSERT INTO members (run_id, idx, name, prompt, status, output)
+                 VALUES (1, 0, 'knuth', 'prompt', 'success', '- P2 naming');",
+            )
+            .unwrap();
+        }
+
+        let history = History::open_at(&path).unwrap();
+        assert_eq!(version(&history), HISTORY_MIGRATIONS.len());
+        let run = history.load(1).unwrap();
+        assert_eq!((run.task.as_str(), run.seed, run.parent_id), ("old task", 7, None));
+        assert!(run.settings.synthesis_template.is_none() && run.settings.synthesis_model.is_none());
+        assert_eq!(run.synthesis.unwrap().text.unwrap(), "All good");
+        assert_eq!((run.members[0].name.as_str(), run.members[0].cached), ("knuth", false));
+        assert_eq!(run.members[0].status, MemberStatus::Success);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,276 @@
+//! Git hooks that have a council review changes before they are committed or pushed.
+
+use crate::findings::{extract_findings, Finding, Priority};
+use crate::run::Run;
+use crate::sandbox::git;
+use clap::ValueEnum;
+use std::path::{Path, PathBuf};
+
+/// First comment line of every hook council installs, so it never removes someone else's.
+const HOOK_MARKER: &str = "# Installed by `council hook install`";
+
+/// Larger diffs are cut off; members can still read the files themselves.
+const MAX_DIFF_BYTES: usize = 200_000;
+
+/// Git's empty tree, the base for a root commit's diff.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// The git hook a council runs from.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookKind {
+    /// Review the staged changes before each commit
+    PreCommit,
+    /// Review the commits being pushed before each push
+    PrePush,
+}
+
+impl HookKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookKind::PreCommit => "pre-commit",
+            HookKind::PrePush => "pre-push",
+        }
+    }
+
+    /// The git command the hook runs before.
+    pub fn action(self) -> &'static str {
+        match self {
+            HookKind::PreCommit => "commit",
+            HookKind::PrePush => "push",
+        }
+    }
+}
+
+/// Which findings of the synthesis stop the commit or push.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockPolicy {
+    /// Block when the synthesis reports a P0 item
+    P0,
+    /// Block when the synthesis reports a P0 or P1 item
+    P1,
+    /// Never block; only show the review
+    Never,
+}
+
+impl BlockPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BlockPolicy::P0 => "p0",
+            BlockPolicy::P1 => "p1",
+            BlockPolicy::Never => "never",
+        }
+    }
+
+    fn blocks(self, priority: Priority) -> bool {
+        match self {
+            BlockPolicy::P0 => priority == Priority::P0,
+            BlockPolicy::P1 => matches!(priority, Priority::P0 | Priority::P1),
+            BlockPolicy::Never => false,
+        }
+    }
+}
+
+/// Where git looks for `kind`, honoring `core.hooksPath`.
+fn hook_path(kind: HookKind) -> Result<PathBuf, String> {
+    let out = git(Path::new("."), &["rev-parse", "--git-path", &format!("hooks/{}", kind.as_str())])
+        .map_err(|e| format!("Not in a git repository ({})", e))?;
+    Ok(PathBuf::from(String::from_utf8_lossy(&out).trim()))
+}
+
+/// The hook file, if council installed it.
+fn is_council_hook(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|script| script.lines().any(|l| l == HOOK_MARKER))
+}
+
+/// Install `kind` to run `command` (a shell command line, whose exit status decides whether
+/// git goes ahead). An existing hook council did not install is left alone unless `force`,
+/// in which case it is kept next to the new one and restored by [`uninstall_hook`].
+pub fn install_hook(kind: HookKind, command: &str, force: bool) -> Result<PathBuf, String> {
+    let path = hook_path(kind)?;
+    if path.exists() && !is_council_hook(&path) {
+        if !force {
+            return Err(format!(
+                "{} already exists and was not installed by council (use --force to replace it; it is kept as {}.council-backup)",
+                path.display(),
+                kind.as_str()
+            ));
+        }
+        let backup = path.with_file_name(format!("{}.council-backup", kind.as_str()));
+        std::fs::rename(&path, &backup).map_err(|e| format!("Cannot back up {}: {}", path.display(), e))?;
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    }
+
+    let script = format!(
+        "#!/bin/sh\n{}\n# Remove with `council hook uninstall --hook {}`; skip once with `git {} --no-verify`.\nexec {}\n",
+        HOOK_MARKER,
+        kind.as_str(),
+        kind.action(),
+        command
+    );
+    std::fs::write(&path, script).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Cannot make {} executable: {}", path.display(), e))?;
+    }
+    Ok(path)
+}
+
+/// Remove the hook council installed as `kind`, restoring the hook it replaced, if any.
+/// Returns the removed hook's path.
+pub fn uninstall_hook(kind: HookKind) -> Result<PathBuf, String> {
+    let path = hook_path(kind)?;
+    if !path.exists() {
+        return Err(format!("No {} hook is installed", kind.as_str()));
+    }
+    if !is_council_hook(&path) {
+        return Err(format!("{} was not installed by council; leaving it alone", path.display()));
+    }
+    std::fs::remove_file(&path).map_err(|e| format!("Cannot remove {}: {}", path.display(), e))?;
+    let backup = path.with_file_name(format!("{}.council-backup", kind.as_str()));
+    if backup.exists() {
+        std::fs::rename(&backup, &path).map_err(|e| format!("Cannot restore {}: {}", backup.display(), e))?;
+    }
+    Ok(path)
+}
+
+/// The diff a `kind` hook reviews: the staged changes before a commit, or every commit being
+/// pushed, read from the `<local ref> <local sha> <remote ref> <remote sha>` lines git gives a
+/// pre-push hook on stdin. A new branch is diffed from where it leaves the remote branches;
+/// deleted refs have nothing to review.
+pub fn changes_to_review(kind: HookKind, pushed: &str) -> Result<String, String> {
+    let here = Path::new(".");
+    let diff = |args: &[&str]| git(here, args).map(|out| String::from_utf8_lossy(&out).into_owned());
+    let zero = |sha: &str| sha.chars().all(|c| c == '0');
+
+    let mut text = match kind {
+        HookKind::PreCommit => diff(&["diff", "--cached"])?,
+        HookKind::PrePush => {
+            let mut text = String::new();
+            for line in pushed.lines() {
+                let [_, local, _, remote] = line.split_whitespace().collect::<Vec<_>>()[..] else { continue };
+                if zero(local) {
+                    continue;
+                }
+                // A remote tip this clone has never fetched is treated like a new branch
+                let known = !zero(remote) && git(here, &["cat-file", "-e", &format!("{}^{{commit}}", remote)]).is_ok();
+                let base = if !known {
+                    let new_commits = diff(&["rev-list", "--reverse", local, "--not", "--remotes"])?;
+                    let Some(first) = new_commits.lines().next() else { continue };
+                    match git(here, &["rev-parse", "--verify", "--quiet", &format!("{}^", first)]) {
+                        Ok(parent) => String::from_utf8_lossy(&parent).trim().to_string(),
+                        Err(_) => EMPTY_TREE.to_string(),
+                    }
+                } else {
+                    remote.to_string()
+                };
+                text.push_str(&diff(&["diff", &base, local])?);
+            }
+            text
+        }
+    };
+
+    if text.len() > MAX_DIFF_BYTES {
+        let mut end = MAX_DIFF_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("\n[diff truncated; read the changed files for the rest]\n");
+    }
+    Ok(text)
+}
+
+/// The task a hook's council reviews `diff` with. The diff is fenced with more backticks than
+/// it contains in a row, so a changed Markdown file cannot close the fence early.
+pub fn hook_task(kind: HookKind, diff: &str) -> String {
+    let longest_run = diff.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    let newline = if diff.is_empty() || diff.ends_with('\n') { "" } else { "\n" };
+    format!(
+        "Review this change before it is {}. Look for bugs, regressions, security problems and missing \
+         handling of edge cases in the changed code; the rest of the repository is available for context. \
+         Mark each issue P0 (must be fixed before the {}), P1 (should be fixed soon) or P2 (minor), and cite \
+         the file:line it concerns.\n\n{}diff\n{}{}{}",
+        match kind {
+            HookKind::PreCommit => "committed",
+            HookKind::PrePush => "pushed",
+        },
+        kind.action(),
+        fence,
+        diff,
+        newline,
+        fence
+    )
+}
+
+/// The findings of `run`'s synthesis that `policy` blocks on. A run without a synthesis
+/// blocks nothing.
+pub fn blocking_findings(run: &Run, policy: BlockPolicy) -> Vec<Finding> {
+    let Some(Ok(text)) = run.synthesis.as_ref().map(|s| s.text.as_ref()) else { return Vec::new() };
+    extract_findings(text)
+        .into_iter()
+        .filter(|f| f.priority.is_some_and(|p| policy.blocks(p)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Usage;
+    use crate::config::Config;
+    use crate::run::{new_run, SynthesisOutput};
+    use std::time::Duration;
+
+    #[test]
+    fn diff_fence_outlasts_backticks_in_the_diff() {
+        let diff = "+++ b/README.md\n+```sh\n+council\n+```\n";
+        let task = hook_task(HookKind::PreCommit, diff);
+        assert!(task.ends_with(&format!("\n\n````diff\n{}````", diff)), "{}", task);
+
+        let task = hook_task(HookKind::PrePush, "+let s = \"`x`\";");
+        assert!(task.contains("before it is pushed"));
+        assert!(task.ends_with("```diff\n+let s = \"`x`\";\n```"), "{}", task);
+    }
+
+    fn reviewed(synthesis: Option<&str>) -> Run {
+        let mut run = new_run(&Config::defaults(), "review".to_string()).unwrap();
+        run.synthesis = synthesis.map(|text| SynthesisOutput {
+            text: Ok(text.to_string()),
+            usage: Usage::default(),
+            elapsed: Duration::ZERO,
+        });
+        run
+    }
+
+    fn blocked(run: &Run, policy: BlockPolicy) -> Vec<String> {
+        blocking_findings(run, policy).into_iter().map(|f| f.text).collect()
+    }
+
+    #[test]
+    fn policy_decides_which_findings_block() {
+        let run = reviewed(Some("## P0\n- Secret committed\n## P1\n- Missing check\n## P2\n- Naming\n"));
+        assert_eq!(blocked(&run, BlockPolicy::P0), vec!["Secret committed"]);
+        assert_eq!(blocked(&run, BlockPolicy::P1), vec!["Secret committed", "Missing check"]);
+        assert!(blocked(&run, BlockPolicy::Never).is_empty());
+
+        let minor = reviewed(Some("- P2 Naming\n- P1 Missing check\n"));
+        assert!(blocked(&minor, BlockPolicy::P0).is_empty());
+        assert_eq!(blocked(&minor, BlockPolicy::P1), vec!["P1 Missing check"]);
+    }
+
+    #[test]
+    fn no_synthesis_blocks_nothing() {
+        assert!(blocked(&reviewed(None), BlockPolicy::P1).is_empty());
+        let mut failed = reviewed(None);
+        failed.synthesis = Some(SynthesisOutput {
+            text: Err("timed out".to_string()),
+            usage: Usage::default(),
+            elapsed: Duration::ZERO,
+        });
+        assert!(blocked(&failed, BlockPolicy::P0).is_empty());
+    }
+}
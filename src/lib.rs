@@ -0,0 +1,57 @@
+//! Council: spawn several Claude instances that analyze a task through orthogonal expert
+//! lenses, then synthesize their analyses into one recommendation.
+//!
+//! A run is built from resolved settings with [`new_run`] (which picks the roster with
+//! [`select_constraints`] and renders each member's prompt), then executed by a [`Council`],
+//! whose progress can be followed as a stream of [`Event`]s:
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), String> {
+//! let config = council::Config::resolve(None)?;
+//! let run = council::new_run(&config, "Review the error handling".to_string())?;
+//! let mut council = council::Council::from_config(&config, run);
+//! let mut events = council.events();
+//! let watch = async {
+//!     while let Some(event) = events.recv().await {
+//!         if let council::Event::MemberFinished(m) = event {
+//!             println!("{} finished: {}", m.name, m.status.as_str());
+//!         }
+//!     }
+//! };
+//! let (run, ()) = tokio::join!(council.execute(), watch);
+//! println!("{}", council::render_report(&run?));
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod backend;
+pub mod batch;
+pub mod cache;
+pub mod citations;
+pub mod config;
+pub mod constraints;
+pub mod fanout;
+pub mod findings;
+pub mod history;
+pub mod hook;
+pub mod prompt;
+pub mod report;
+pub mod run;
+pub mod runner;
+pub mod sandbox;
+pub mod watch;
+
+pub use backend::{backend_installed, run_claude, Backend, Permission, StreamProgress, Usage};
+pub use batch::{load_batch, render_batch_index, BatchEntry, BatchResult};
+pub use citations::{annotate_citations, verify_citations, Citation, SourceCitations};
+pub use config::Config;
+pub use constraints::{select_constraints, Constraint, CONSTRAINTS};
+pub use fanout::{combine_runs, discover_dirs, scoped_task};
+pub use history::History;
+pub use hook::{blocking_findings, changes_to_review, hook_task, install_hook, uninstall_hook, BlockPolicy, HookKind};
+pub use prompt::{create_prompt, create_synthesis_prompt, load_synthesis_template, PromptTemplate};
+pub use report::{event_json, render_report, review_comments_json, run_json};
+pub use run::{new_run, Budget, MemberOutput, MemberStatus, Run, RunSettings, SynthesisOutput};
+pub use runner::{synthesize, CancelHandle, Council, Event};
+pub use sandbox::Isolation;
+pub use watch::{changed_files, merge_rerun, relevant_lenses, rerun, snapshot, Snapshot};
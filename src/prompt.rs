@@ -0,0 +1,414 @@
+//! Member and synthesis prompts, and the templates they are rendered from.
+
+use crate::constraints::{Constraint, CONSTRAINTS};
+use crate::run::MemberOutput;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Placeholders available to member prompt templates.
+pub const MEMBER_PLACEHOLDERS: [&str; 5] = ["constraint_prompt", "task", "num_members", "name", "context"];
+
+/// The member prompt used unless `--member-template` replaces it.
+pub const DEFAULT_MEMBER_TEMPLATE: &str = r#"You are a council member analyzing with a specific constraint. There are {num_members} council members, each with different orthogonal constraints.
+
+{constraint_prompt}
+
+YOUR TASK:
+{task}{context}
+
+YOUR OUTPUT REQUIREMENTS:
+1. Executive summary (2-3 sentences) from your constraint's perspective ONLY
+2. Detailed analysis with specific insights labeled [{name}]
+3. Recommendations with file paths and line numbers where applicable
+4. Risks and trade-offs within your constraint area
+
+Quality over quantity - 5 constraint-specific insights > 20 generic observations.
+If your analysis could come from any other constraint, you're doing it WRONG."#;
+
+/// Member prompt overrides from `--member-template`.
+#[derive(Default)]
+pub struct MemberTemplates {
+    pub global: Option<PromptTemplate>,
+    pub per_constraint: HashMap<String, PromptTemplate>,
+}
+
+impl MemberTemplates {
+    /// Parse `PATH` (every member) and `CONSTRAINT=PATH` (one lens) specs, validating each template.
+    pub fn load(specs: &[String]) -> Result<MemberTemplates, String> {
+        let mut templates = MemberTemplates::default();
+        for spec in specs {
+            let (constraint, path) = match spec.split_once('=') {
+                Some((name, path)) => {
+                    if !CONSTRAINTS.iter().any(|c| c.name == name) {
+                        return Err(format!("--member-template {}: unknown constraint '{}'", spec, name));
+                    }
+                    (Some(name), path)
+                }
+                None => (None, spec.as_str()),
+            };
+            let template = PromptTemplate {
+                name: path.to_string(),
+                text: std::fs::read_to_string(path)
+                    .map_err(|e| format!("Cannot read member template {}: {}", path, e))?,
+            };
+            validate_template(&template, &MEMBER_PLACEHOLDERS)?;
+            match constraint {
+                Some(name) => {
+                    templates.per_constraint.insert(name.to_string(), template);
+                }
+                None => templates.global = Some(template),
+            }
+        }
+        Ok(templates)
+    }
+
+    pub fn for_constraint(&self, name: &str) -> Option<&PromptTemplate> {
+        self.per_constraint.get(name).or(self.global.as_ref())
+    }
+}
+
+/// The `{context}` block: every `--file` under its own heading, or empty.
+pub fn read_context_files(paths: &[PathBuf]) -> Result<String, String> {
+    let mut context = String::new();
+    for path in paths {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        context.push_str(&format!("\n\n--- {} ---\n{}", path.display(), text.trim_end()));
+    }
+    if context.is_empty() {
+        Ok(context)
+    } else {
+        Ok(format!("\n\nCONTEXT FILES:{}", context))
+    }
+}
+
+/// One member's prompt: its constraint, the task and any context files, rendered into the
+/// member template.
+pub fn create_prompt(
+    constraint: &Constraint,
+    task: &str,
+    num_members: usize,
+    template: Option<&PromptTemplate>,
+    context: &str,
+) -> String {
+    render_template(
+        template.map(|t| t.text.as_str()).unwrap_or(DEFAULT_MEMBER_TEMPLATE),
+        &[
+            ("constraint_prompt", constraint.prompt),
+            ("task", task),
+            ("num_members", &num_members.to_string()),
+            ("name", constraint.name),
+            ("context", context),
+        ],
+    )
+}
+
+/// Placeholders available to synthesis templates.
+pub const SYNTHESIS_PLACEHOLDERS: [&str; 4] = ["task", "num_members", "members", "analyses"];
+
+const DEFAULT_SYNTHESIS_TEMPLATE: &str = r#"You are a master synthesizer analyzing insights from {num_members} council members who each analyzed through different constraints.
+
+YOUR TASK:
+Synthesize the following analyses into ONE coherent, actionable recommendation.
+
+ORIGINAL TASK:
+{task}
+
+COUNCIL ANALYSES:
+{analyses}
+
+YOUR SYNTHESIS REQUIREMENTS:
+
+1. EXECUTIVE SUMMARY (3-4 sentences)
+   - What's the core issue?
+   - What's the recommended solution?
+   - What's the expected impact?
+
+2. CONSOLIDATED FINDINGS
+   - Identify common themes across multiple constraints
+   - Highlight unique insights from specific constraints
+   - Resolve any conflicting recommendations (explain which to prioritize and why)
+
+3. PRIORITIZED ACTION PLAN
+   - List specific changes in priority order (P0/P1/P2)
+   - For each item: file:line, what to change, why, expected impact
+   - Include concrete code snippets where applicable
+
+4. RISKS & TRADE-OFFS
+   - What are we trading off?
+   - What could go wrong?
+   - How to mitigate?
+
+5. IMPLEMENTATION ROADMAP
+   - What order to tackle changes?
+   - What dependencies exist?
+
+Be concise but specific. The goal is ONE clear path forward, not multiple options.
+Focus on ACTIONABLE recommendations with clear next steps."#;
+
+const ADR_SYNTHESIS_TEMPLATE: &str = r#"You are a master synthesizer turning the analyses of {num_members} council members ({members}) into an Architecture Decision Record.
+
+ORIGINAL TASK:
+{task}
+
+COUNCIL ANALYSES:
+{analyses}
+
+Write ONE ADR in Markdown with exactly these sections:
+
+# ADR: <short decision title>
+
+## Status
+Proposed
+
+## Context
+The forces at play, drawn from the analyses. Cite file:line where the council did.
+
+## Decision
+The single decision the council's evidence supports, stated in active voice ("We will ...").
+
+## Consequences
+What becomes easier and what becomes harder. Include the risks the council raised.
+
+## Alternatives Considered
+Options the council raised and why they were not chosen.
+
+Do not list multiple decisions. If the council disagrees, decide and say why."#;
+
+const REVIEW_SYNTHESIS_TEMPLATE: &str = r#"You are a senior reviewer consolidating {num_members} council analyses ({members}) into a pull request review checklist.
+
+ORIGINAL TASK:
+{task}
+
+COUNCIL ANALYSES:
+{analyses}
+
+Output a Markdown checklist grouped under these headings, most important first:
+
+## Blocking (P0)
+## Should fix (P1)
+## Nits (P2)
+
+Each item is one line: `- [ ] path:line - what to change and why`. Merge duplicates raised by
+several members into one item. Omit empty groups. End with a one-line overall verdict:
+APPROVE, REQUEST CHANGES or COMMENT."#;
+
+const VERDICT_SYNTHESIS_TEMPLATE: &str = r#"You are a master synthesizer reading the analyses of {num_members} council members ({members}).
+
+ORIGINAL TASK:
+{task}
+
+COUNCIL ANALYSES:
+{analyses}
+
+Reply with ONE paragraph of at most 120 words: start with a one-word verdict (GO, NO-GO or
+CONDITIONAL), then the single most important reason, then the one change that matters most.
+No headings, no lists."#;
+
+/// Built-in synthesis templates selectable by name with `--synthesis-template`.
+pub const SYNTHESIS_TEMPLATES: [(&str, &str); 4] = [
+    ("default", DEFAULT_SYNTHESIS_TEMPLATE),
+    ("adr", ADR_SYNTHESIS_TEMPLATE),
+    ("review", REVIEW_SYNTHESIS_TEMPLATE),
+    ("verdict", VERDICT_SYNTHESIS_TEMPLATE),
+];
+
+/// A prompt template and where it came from (built-in name or file path).
+#[derive(Clone, Debug)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub text: String,
+}
+
+/// Names of `{placeholder}`s in `template`; `{{` and `}}` are literal braces.
+fn template_placeholders(template: &str) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    let mut chars = template.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '{' if chars.peek().map(|(_, n)| *n) == Some('{') => {
+                chars.next();
+            }
+            '}' if chars.peek().map(|(_, n)| *n) == Some('}') => {
+                chars.next();
+            }
+            '{' => {
+                let rest = &template[start + 1..];
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| format!("Unclosed '{{' at byte {} (use '{{{{' for a literal brace)", start))?;
+                names.push(rest[..end].to_string());
+                for _ in 0..=rest[..end].chars().count() {
+                    chars.next();
+                }
+            }
+            '}' => return Err(format!("Unmatched '}}' at byte {} (use '}}}}' for a literal brace)", start)),
+            _ => {}
+        }
+    }
+    Ok(names)
+}
+
+/// Check every placeholder in `template` is one of `allowed`.
+pub fn validate_template(template: &PromptTemplate, allowed: &[&str]) -> Result<(), String> {
+    let names = template_placeholders(&template.text).map_err(|e| format!("Template {}: {}", template.name, e))?;
+    let unknown: Vec<_> = names.iter().filter(|n| !allowed.contains(&n.as_str())).collect();
+    if !unknown.is_empty() {
+        return Err(format!(
+            "Template {} uses unknown placeholder(s) {} (available: {})",
+            template.name,
+            unknown.iter().map(|n| format!("{{{}}}", n)).collect::<Vec<_>>().join(", "),
+            allowed.iter().map(|n| format!("{{{}}}", n)).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Substitute placeholders in a validated template.
+pub fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        match (tail.starts_with('{'), tail.find('}')) {
+            (true, Some(end)) => {
+                let name = &tail[1..end];
+                match values.iter().find(|(k, _)| *k == name) {
+                    Some((_, v)) => out.push_str(v),
+                    None => out.push_str(&tail[..=end]),
+                }
+                rest = &tail[end + 1..];
+            }
+            _ => {
+                out.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Resolve `--synthesis-template`: a built-in name or a path to a template file.
+pub fn load_synthesis_template(spec: &str) -> Result<PromptTemplate, String> {
+    let template = match SYNTHESIS_TEMPLATES.iter().find(|(name, _)| *name == spec) {
+        Some((name, text)) => PromptTemplate { name: name.to_string(), text: text.to_string() },
+        None => PromptTemplate {
+            name: spec.to_string(),
+            text: std::fs::read_to_string(spec).map_err(|e| {
+                format!(
+                    "Cannot read synthesis template {}: {} (built-in templates: {})",
+                    spec,
+                    e,
+                    SYNTHESIS_TEMPLATES.iter().map(|(n, _)| *n).collect::<Vec<_>>().join(", ")
+                )
+            })?,
+        },
+    };
+    validate_template(&template, &SYNTHESIS_PLACEHOLDERS)?;
+    Ok(template)
+}
+
+/// The synthesis prompt over `outputs`, with optional extra instructions appended.
+pub fn create_synthesis_prompt(
+    outputs: &[&MemberOutput],
+    task: &str,
+    template: Option<&PromptTemplate>,
+    instructions: Option<&str>,
+) -> String {
+    let analyses: String = outputs
+        .iter()
+        .map(|m| {
+            format!(
+                "═══════════════════════════════════════════════════════════════\nMEMBER #{}: {}\n═══════════════════════════════════════════════════════════════\n\n{}",
+                m.id + 1,
+                m.name.to_uppercase(),
+                m.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let members = outputs
+        .iter()
+        .map(|m| format!("#{} {}", m.id + 1, m.name.to_uppercase()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let num_members = outputs.len().to_string();
+
+    let prompt = render_template(
+        template.map(|t| t.text.as_str()).unwrap_or(DEFAULT_SYNTHESIS_TEMPLATE),
+        &[("task", task), ("num_members", &num_members), ("members", &members), ("analyses", &analyses)],
+    );
+    match instructions {
+        Some(i) => format!("{}\n\nADDITIONAL INSTRUCTIONS:\n{}", prompt, i),
+        None => prompt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(text: &str) -> PromptTemplate {
+        PromptTemplate { name: "test.txt".to_string(), text: text.to_string() }
+    }
+
+    #[test]
+    fn placeholders_skip_escaped_braces() {
+        let names = template_placeholders("{task} {{literal}} {analyses} }} {{").unwrap();
+        assert_eq!(names, ["task", "analyses"]);
+    }
+
+    #[test]
+    fn placeholders_reject_unbalanced_braces() {
+        assert!(template_placeholders("Task: {task").unwrap_err().contains("Unclosed '{'"));
+        assert!(template_placeholders("Task: task}").unwrap_err().contains("Unmatched '}'"));
+    }
+
+    #[test]
+    fn validate_names_unknown_placeholders() {
+        assert!(validate_template(&template("{task}\n{members}"), &SYNTHESIS_PLACEHOLDERS).is_ok());
+        let e = validate_template(&template("{task} {verdict}"), &SYNTHESIS_PLACEHOLDERS).unwrap_err();
+        assert!(e.contains("test.txt") && e.contains("{verdict}"), "{}", e);
+    }
+
+    #[test]
+    fn built_in_synthesis_templates_are_valid() {
+        for (name, text) in SYNTHESIS_TEMPLATES {
+            let t = PromptTemplate { name: name.to_string(), text: text.to_string() };
+            assert!(validate_template(&t, &SYNTHESIS_PLACEHOLDERS).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn render_substitutes_known_placeholders_once() {
+        let out = render_template("{task} {{x}} {unknown} }}", &[("task", "fix {analyses}"), ("analyses", "A")]);
+        // Values are inserted as-is, not expanded again
+        assert_eq!(out, "fix {analyses} {x} {unknown} }");
+    }
+
+    #[test]
+    fn default_member_template_is_valid() {
+        let t = PromptTemplate { name: "default".to_string(), text: DEFAULT_MEMBER_TEMPLATE.to_string() };
+        assert!(validate_template(&t, &MEMBER_PLACEHOLDERS).is_ok());
+    }
+
+    #[test]
+    fn member_template_renders_lens_and_context() {
+        let lens = &CONSTRAINTS[0];
+        let t = template("[{name}] {task} ({num_members}){context}");
+        let prompt = create_prompt(lens, "Review", 3, Some(&t), "\n\nCONTEXT FILES:");
+        assert_eq!(prompt, format!("[{}] Review (3)\n\nCONTEXT FILES:", lens.name));
+    }
+
+    #[test]
+    fn member_templates_reject_unknown_lenses() {
+        let e = MemberTemplates::load(&["no_such_lens=prompt.txt".to_string()]).err().unwrap();
+        assert!(e.contains("unknown constraint 'no_such_lens'"), "{}", e);
+    }
+}
@@ -0,0 +1,313 @@
+//! Reports of a run: Markdown (with its citations checked), JSON, the cost table rows behind both,
+//! events as JSON, and its findings as review comments.
+
+use crate::backend::{StreamProgress, Usage};
+use crate::citations::{annotate_citations, verify_citations};
+use crate::findings::{extract_findings, Location, Priority};
+use crate::run::{MemberOutput, MemberStatus, Run, SynthesisOutput};
+use crate::runner::Event;
+use crate::sandbox::git_toplevel;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Token counts as `950`, `12.3k` or `1.25M`.
+pub fn format_tokens(tokens: u64) -> String {
+    if tokens >= 1_000_000 {
+        format!("{:.2}M", tokens as f64 / 1_000_000.0)
+    } else if tokens >= 1_000 {
+        format!("{:.1}k", tokens as f64 / 1_000.0)
+    } else {
+        tokens.to_string()
+    }
+}
+
+/// Rows of (label, time, input, output, cost) for every member, synthesis and the total.
+pub fn cost_rows(run: &Run) -> Vec<[String; 5]> {
+    let row = |label: String, elapsed: Option<Duration>, usage: &Usage| {
+        [
+            label,
+            elapsed.map(|d| format!("{:.1}s", d.as_secs_f64())).unwrap_or_default(),
+            format_tokens(usage.input_tokens),
+            format_tokens(usage.output_tokens),
+            format!("${:.4}", usage.cost_usd),
+        ]
+    };
+
+    let mut total = Usage::default();
+    let mut rows = Vec::with_capacity(run.members.len() + 2);
+    for m in &run.members {
+        total += m.usage;
+        let elapsed = m.status.has_output().then_some(m.elapsed);
+        rows.push(row(format!("#{} {}", m.id + 1, m.name), elapsed, &m.usage));
+    }
+    if let Some(s) = &run.synthesis {
+        total += s.usage;
+        rows.push(row("synthesis".to_string(), Some(s.elapsed), &s.usage));
+    }
+    rows.push(row("TOTAL".to_string(), None, &total));
+    rows
+}
+
+/// The run as a Markdown report: task, synthesis, every finished analysis, how well each one's
+/// `path:line` citations hold up in the working tree (invalid ones are marked in the text) and
+/// costs.
+pub fn render_report(run: &Run) -> String {
+    let checked = verify_citations(run);
+    let annotate = |member: Option<usize>, text: &str| match checked.iter().find(|s| s.member == member) {
+        Some(source) => annotate_citations(text, &source.citations),
+        None => text.to_string(),
+    };
+    let mut md = String::new();
+    md.push_str("# Council Report\n\n");
+    md.push_str(&format!("## Task\n\n{}\n\n", run.task));
+
+    if let Some(note) = &run.budget_note {
+        md.push_str(&format!("> **Budget exceeded:** {}. Cancelled members were not synthesized.\n\n", note));
+    }
+
+    if let Some(s) = &run.synthesis {
+        md.push_str("## Synthesis & Recommendations\n\n");
+        match &s.text {
+            Ok(text) => md.push_str(annotate(None, text).trim()),
+            Err(e) => md.push_str(&format!("_Synthesis failed: {}_", e)),
+        }
+        md.push_str("\n\n");
+    }
+
+    md.push_str("## Member Analyses\n\n");
+    for m in run.finished_members() {
+        let text = annotate(Some(m.id), &m.text);
+        md.push_str(&format!("### Member #{}: {}\n\n{}\n\n", m.id + 1, m.name.to_uppercase(), text.trim()));
+    }
+
+    if checked.iter().any(|s| !s.citations.is_empty()) {
+        md.push_str("## Citations\n\n");
+        md.push_str("`path:line` references checked against the working tree.\n\n");
+        md.push_str("| Source | Citations | Valid | Accuracy |\n");
+        md.push_str("|---|---:|---:|---:|\n");
+        for s in &checked {
+            let label = match s.member {
+                Some(id) => format!("#{} {}", id + 1, s.name),
+                None => s.name.clone(),
+            };
+            let accuracy = s.accuracy().map(|a| format!("{:.0}%", a * 100.0)).unwrap_or_else(|| "-".to_string());
+            md.push_str(&format!("| {} | {} | {} | {} |\n", label, s.citations.len(), s.valid(), accuracy));
+        }
+        md.push('\n');
+    }
+
+    md.push_str("## Costs\n\n");
+    md.push_str("| Member | Time | Input tokens | Output tokens | Cost |\n");
+    md.push_str("|---|---:|---:|---:|---:|\n");
+    for r in cost_rows(run) {
+        md.push_str(&format!("| {} | {} | {} | {} | {} |\n", r[0], r[1], r[2], r[3], r[4]));
+    }
+    md
+}
+
+/// The run as JSON: settings, every member's output and usage, and the synthesis.
+pub fn run_json(run: &Run) -> serde_json::Value {
+    let members: Vec<serde_json::Value> = run.members.iter().map(member_json).collect();
+    let synthesis = run.synthesis.as_ref().map(synthesis_json);
+    serde_json::json!({
+        "id": run.id,
+        "parent_id": run.parent_id,
+        "created_at": run.created_at,
+        "task": run.task,
+        "seed": run.seed,
+        "workdir": run.workdir.display().to_string(),
+        "model": run.settings.model,
+        "budget_note": run.budget_note,
+        "members": members,
+        "synthesis": synthesis,
+        "total_elapsed_secs": run.total_elapsed.as_secs_f64(),
+        "usage": usage_json(&run.total_usage()),
+    })
+}
+
+fn usage_json(u: &Usage) -> serde_json::Value {
+    serde_json::json!({ "input_tokens": u.input_tokens, "output_tokens": u.output_tokens, "cost_usd": u.cost_usd })
+}
+
+fn member_json(m: &MemberOutput) -> serde_json::Value {
+    serde_json::json!({
+        "id": m.id + 1,
+        "name": m.name,
+        "model": m.model,
+        "status": m.status.as_str(),
+        "cached": m.cached,
+        "elapsed_secs": m.elapsed.as_secs_f64(),
+        "usage": usage_json(&m.usage),
+        "text": m.text,
+    })
+}
+
+fn synthesis_json(s: &SynthesisOutput) -> serde_json::Value {
+    serde_json::json!({
+        "text": s.text.as_ref().ok(),
+        "error": s.text.as_ref().err(),
+        "elapsed_secs": s.elapsed.as_secs_f64(),
+        "usage": usage_json(&s.usage),
+    })
+}
+
+fn progress_json(p: &StreamProgress) -> serde_json::Value {
+    serde_json::json!({ "bytes": p.bytes, "output_tokens": p.output_tokens, "tool": p.tool, "text": p.text })
+}
+
+/// An event from `run`'s council as JSON: `{"event": <name>, ...}`, with members numbered from 1
+/// as everywhere else. `run` must be up to date with the event (see `Run::apply`); `run_finished`
+/// carries the whole run as `run_json` renders it.
+pub fn event_json(event: &Event, run: &Run) -> serde_json::Value {
+    let member = |id: usize| {
+        let name = run.members.get(id).map(|m| m.name.as_str());
+        serde_json::json!({ "id": id + 1, "name": name })
+    };
+    let mut json = match event {
+        Event::MemberQueued(id) | Event::MemberStarted(id) => member(*id),
+        Event::MemberChunk(id, p) => {
+            let mut json = progress_json(p);
+            json["id"] = serde_json::json!(id + 1);
+            json
+        }
+        Event::MemberFinished(m) => serde_json::json!({ "member": member_json(m) }),
+        Event::SandboxChanges(id, files) => {
+            let mut json = member(*id);
+            json["files"] = serde_json::json!(files);
+            json
+        }
+        Event::MembersDone { elapsed, budget_note } => {
+            serde_json::json!({ "elapsed_secs": elapsed.as_secs_f64(), "budget_note": budget_note })
+        }
+        Event::SynthesisStarted => serde_json::json!({}),
+        Event::SynthesisChunk(p) => progress_json(p),
+        Event::SynthesisFinished(s) => serde_json::json!({ "synthesis": synthesis_json(s) }),
+        Event::RunFinished { .. } => serde_json::json!({ "run": run_json(run) }),
+        Event::Warning(message) => serde_json::json!({ "message": message }),
+    };
+    json["event"] = serde_json::json!(event.name());
+    json
+}
+
+/// A cited path as the repository sees it: relative to its root rather than to `workdir`.
+fn repo_path(path: &str, workdir: &Path, toplevel: Option<&Path>) -> String {
+    let path = Path::new(path.trim_start_matches("./"));
+    let Some(top) = toplevel else { return path.display().to_string() };
+    let full = if path.is_absolute() { path.to_path_buf() } else { workdir.join(path) };
+    match full.strip_prefix(top) {
+        Ok(rel) => rel.display().to_string(),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+/// The run's findings as a pull request review in the shape GitHub's "create a review" API
+/// takes: one inline comment (`path`, `line`, `side`, and `start_line` for ranges) per cited
+/// location, with paths from the repository root, and findings that cite no line, or a file or
+/// line that does not exist (GitHub rejects the whole review over one), listed in the review body. Findings come from the synthesis, or from every analysis when `members` is set
+/// or there is no synthesis. Nothing is sent anywhere.
+pub fn review_comments_json(run: &Run, members: bool) -> serde_json::Value {
+    let synthesis = run.synthesis.as_ref().and_then(|s| s.text.as_ref().ok());
+    let mut sources: Vec<(Option<&str>, &str)> = synthesis.map(|text| (None, text.as_str())).into_iter().collect();
+    if members || synthesis.is_none() {
+        sources.extend(
+            run.members
+                .iter()
+                .filter(|m| m.status == MemberStatus::Success)
+                .map(|m| (Some(m.name.as_str()), m.text.as_str())),
+        );
+    }
+
+    let checked = verify_citations(run);
+    let problem = |author: Option<&str>, loc: &Location| {
+        let source = checked.iter().find(|s| match author {
+            Some(name) => s.member.is_some() && s.name == name,
+            None => s.member.is_none(),
+        })?;
+        source.citations.iter().find(|c| c.location == *loc)?.problem.clone()
+    };
+
+    let toplevel = git_toplevel(&run.workdir);
+    let mut comments: BTreeMap<(String, u32, Option<u32>), Vec<String>> = BTreeMap::new();
+    let mut general = Vec::new();
+    for (author, text) in sources {
+        for f in extract_findings(text) {
+            let mut body = match f.priority {
+                Some(p) if Priority::find(&f.text) != Some(p) => format!("**{}** {}", p.as_str(), f.text),
+                _ => f.text.clone(),
+            };
+            if let Some(author) = author {
+                body.push_str(&format!(" _({})_", author));
+            }
+            let invalid = f.location.as_ref().and_then(|loc| problem(author, loc));
+            match (f.location, invalid) {
+                (Some(_), Some(problem)) => general.push(format!("{} _(invalid citation: {})_", body, problem)),
+                (Some(loc), None) => {
+                    let path = repo_path(&loc.path, &run.workdir, toplevel.as_deref());
+                    let end_line = loc.end_line.filter(|end| *end > loc.line);
+                    comments.entry((path, loc.line, end_line)).or_default().push(body);
+                }
+                (None, _) => general.push(body),
+            }
+        }
+    }
+
+    let comments: Vec<serde_json::Value> = comments
+        .into_iter()
+        .map(|((path, line, end_line), bodies)| {
+            let body = bodies.join("\n\n");
+            match end_line {
+                Some(end) => serde_json::json!({
+                    "path": path, "start_line": line, "start_side": "RIGHT", "line": end, "side": "RIGHT", "body": body,
+                }),
+                None => serde_json::json!({ "path": path, "line": line, "side": "RIGHT", "body": body }),
+            }
+        })
+        .collect();
+
+    let task = run.task.lines().next().unwrap_or_default();
+    let mut body = match run.id {
+        Some(id) => format!("Council review (run #{}): {}", id, task),
+        None => format!("Council review: {}", task),
+    };
+    if !general.is_empty() {
+        body.push_str("\n\n");
+        body.push_str(&general.iter().map(|b| format!("- {}", b)).collect::<Vec<_>>().join("\n"));
+    }
+    serde_json::json!({ "body": body, "event": "COMMENT", "comments": comments })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::run::new_run;
+
+    #[test]
+    fn review_comments_only_point_at_lines_that_exist() {
+        let dir = std::env::temp_dir().join(format!("council-report-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}\nfn b() {}\nfn c() {}\n").unwrap();
+
+        let mut run = new_run(&Config::defaults(), "Review".to_string()).unwrap();
+        run.workdir = dir.clone();
+        let text = "- P1 src/lib.rs:2 unchecked input\n- P1 src/lib.rs:40 past the end\n\
+                    - P2 src/gone.rs:1 deleted file\n- P0 no location at all\n";
+        run.synthesis = Some(SynthesisOutput {
+            text: Ok(text.to_string()),
+            usage: Usage::default(),
+            elapsed: Duration::ZERO,
+        });
+        let review = review_comments_json(&run, false);
+
+        let comments = review["comments"].as_array().unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!((comments[0]["path"].as_str(), comments[0]["line"].as_u64()), (Some("src/lib.rs"), Some(2)));
+        let body = review["body"].as_str().unwrap();
+        assert!(body.contains("src/lib.rs:40 past the end _(invalid citation: the file has 3 lines)_"), "{}", body);
+        assert!(body.contains("src/gone.rs:1 deleted file _(invalid citation: no such file)_"), "{}", body);
+        assert!(body.contains("no location at all"), "{}", body);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+//! A council run: its roster, settings, member outputs and synthesis.
+
+use crate::backend::{Backend, Permission, Usage};
+use crate::config::Config;
+use crate::constraints::select_constraints;
+use crate::prompt::{create_prompt, load_synthesis_template, read_context_files, MemberTemplates, PromptTemplate};
+use crate::report::format_tokens;
+use crate::sandbox::Isolation;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Spending limits checked as members complete. Clones share what has been spent, so one
+/// budget can cover several councils.
+#[derive(Clone, Debug, Default)]
+pub struct Budget {
+    pub max_cost: Option<f64>,
+    pub max_tokens: Option<u64>,
+    /// Synthesis does not start once a limit is reached, as for a directory council whose
+    /// synthesis is only one analysis of a larger review; otherwise it still runs over what finished
+    pub covers_synthesis: bool,
+    spent: Arc<Spent>,
+}
+
+#[derive(Debug, Default)]
+struct Spent {
+    usage: Mutex<Usage>,
+    /// Woken when spending grows, so councils sharing the budget notice it ran out
+    grew: Notify,
+}
+
+impl Budget {
+    pub fn new(max_cost: Option<f64>, max_tokens: Option<u64>) -> Budget {
+        Budget { max_cost, max_tokens, covers_synthesis: false, spent: Arc::default() }
+    }
+
+    /// Count `usage` against the limits.
+    pub fn spend(&self, usage: Usage) {
+        *self.spent.usage.lock().unwrap() += usage;
+        self.spent.grew.notify_waiters();
+    }
+
+    /// Describe which limit the spending so far has reached, if any.
+    pub fn exceeded(&self) -> Option<String> {
+        let spent = *self.spent.usage.lock().unwrap();
+        if let Some(max) = self.max_cost {
+            if spent.cost_usd >= max {
+                return Some(format!("spent ${:.4} of ${:.2}", spent.cost_usd, max));
+            }
+        }
+        if let Some(max) = self.max_tokens {
+            if spent.total_tokens() >= max {
+                return Some(format!(
+                    "used {} of {} tokens",
+                    format_tokens(spent.total_tokens()),
+                    format_tokens(max)
+                ));
+            }
+        }
+        None
+    }
+
+    /// Wait until a limit is reached, by this council or another sharing the budget.
+    pub async fn exhausted(&self) -> String {
+        loop {
+            // Registered before checking, so spending in between is not missed
+            let grew = self.spent.grew.notified();
+            if let Some(reason) = self.exceeded() {
+                return reason;
+            }
+            grew.await;
+        }
+    }
+}
+
+/// How a member's run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberStatus {
+    /// Not finished yet (or the council was interrupted)
+    Pending,
+    Success,
+    Failed,
+    TimedOut,
+    /// Stopped by the budget before it finished
+    Cancelled,
+}
+
+impl MemberStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MemberStatus::Pending => "pending",
+            MemberStatus::Success => "success",
+            MemberStatus::Failed => "failed",
+            MemberStatus::TimedOut => "timed-out",
+            MemberStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(s: &str) -> MemberStatus {
+        match s {
+            "success" => MemberStatus::Success,
+            "failed" => MemberStatus::Failed,
+            "timed-out" => MemberStatus::TimedOut,
+            "cancelled" => MemberStatus::Cancelled,
+            _ => MemberStatus::Pending,
+        }
+    }
+
+    /// Whether the member produced something synthesis can use (errors are passed on too)
+    pub fn has_output(self) -> bool {
+        !matches!(self, MemberStatus::Pending | MemberStatus::Cancelled)
+    }
+}
+
+/// One council member: its assignment and, once finished, its analysis.
+#[derive(Clone)]
+pub struct MemberOutput {
+    pub id: usize,
+    pub name: String,
+    pub model: Option<String>,
+    pub prompt: String,
+    pub status: MemberStatus,
+    pub text: String,
+    pub usage: Usage,
+    pub elapsed: Duration,
+    /// Output was reused from the member cache instead of spawning `claude`
+    pub cached: bool,
+}
+
+/// The synthesis step's result.
+#[derive(Clone)]
+pub struct SynthesisOutput {
+    pub text: Result<String, String>,
+    pub usage: Usage,
+    pub elapsed: Duration,
+}
+
+/// Settings a run was started with.
+#[derive(Clone)]
+pub struct RunSettings {
+    pub timeout: u64,
+    pub model: Option<String>,
+    pub permissions: Permission,
+    pub synthesis_permissions: Permission,
+    pub isolation: Isolation,
+    pub synthesize: bool,
+    /// Model for synthesis when it differs from the members' model
+    pub synthesis_model: Option<String>,
+    /// Extra guidance appended to the synthesis prompt
+    pub synthesis_instructions: Option<String>,
+    /// Replaces the default synthesis prompt structure
+    pub synthesis_template: Option<PromptTemplate>,
+    /// Not stored in history; loaded runs take it from the current config
+    pub backend: Backend,
+}
+
+/// Everything about one council run, as printed, reported and stored.
+#[derive(Clone)]
+pub struct Run {
+    /// Row id in the history database, once saved
+    pub id: Option<i64>,
+    /// Run whose member outputs this run re-synthesized
+    pub parent_id: Option<i64>,
+    /// Unix timestamp of when the run started
+    pub created_at: i64,
+    pub task: String,
+    pub seed: u64,
+    /// Directory the council was run from
+    pub workdir: PathBuf,
+    pub settings: RunSettings,
+    pub members: Vec<MemberOutput>,
+    pub synthesis: Option<SynthesisOutput>,
+    pub member_elapsed: Duration,
+    pub total_elapsed: Duration,
+    /// Why the budget cut the run short, if it did
+    pub budget_note: Option<String>,
+}
+
+impl RunSettings {
+    /// The settings of a new run, loading its synthesis template.
+    pub fn from_config(config: &Config) -> Result<RunSettings, String> {
+        Ok(RunSettings {
+            timeout: config.timeout,
+            model: config.model.clone(),
+            permissions: config.permissions,
+            synthesis_permissions: config.synthesis_permissions,
+            isolation: config.isolate,
+            synthesize: config.synthesize,
+            synthesis_model: None,
+            synthesis_instructions: None,
+            synthesis_template: config.synthesis_template.as_deref().map(load_synthesis_template).transpose()?,
+            backend: config.backend.clone(),
+        })
+    }
+}
+
+impl Run {
+    /// Members whose analyses are handed to synthesis.
+    pub fn finished_members(&self) -> Vec<&MemberOutput> {
+        self.members.iter().filter(|m| m.status.has_output()).collect()
+    }
+
+    /// Tokens and cost of every member and the synthesis.
+    pub fn total_usage(&self) -> Usage {
+        let mut total = Usage::default();
+        self.members.iter().for_each(|m| total += m.usage);
+        if let Some(s) = &self.synthesis {
+            total += s.usage;
+        }
+        total
+    }
+}
+
+/// Build a new run from resolved settings: templates, context files, roster and prompts.
+/// Everything that can be wrong with the settings fails here, before any process is spawned.
+pub fn new_run(config: &Config, task: String) -> Result<Run, String> {
+    let settings = RunSettings::from_config(config)?;
+    let member_templates = MemberTemplates::load(&config.member_template)?;
+    let context = read_context_files(&config.file)?;
+
+    let seed = config.seed.unwrap_or_else(rand::random);
+    let constraints = select_constraints(config.num, seed);
+    let num_members = constraints.len();
+
+    let members: Vec<MemberOutput> = constraints
+        .iter()
+        .enumerate()
+        .map(|(i, constraint)| MemberOutput {
+            id: i,
+            name: constraint.name.to_string(),
+            model: config.model.clone(),
+            prompt: create_prompt(
+                constraint,
+                &task,
+                num_members,
+                member_templates.for_constraint(constraint.name),
+                &context,
+            ),
+            status: MemberStatus::Pending,
+            text: String::new(),
+            usage: Usage::default(),
+            elapsed: Duration::ZERO,
+            cached: false,
+        })
+        .collect();
+
+    Ok(Run {
+        id: None,
+        parent_id: None,
+        created_at: unix_now(),
+        task,
+        seed,
+        workdir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        settings,
+        members,
+        synthesis: None,
+        member_elapsed: Duration::ZERO,
+        total_elapsed: Duration::ZERO,
+        budget_note: None,
+    })
+}
+
+pub fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,516 @@
+//! The council runner: members in parallel, then synthesis, reported as a stream of events.
+
+use crate::backend::{run_claude, ClaudeError, StreamProgress, Usage};
+use crate::cache::MemberCache;
+use crate::config::Config;
+use crate::history::History;
+use crate::prompt::create_synthesis_prompt;
+use crate::run::{Budget, MemberOutput, MemberStatus, Run, SynthesisOutput};
+use crate::sandbox::{Isolation, Sandbox};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Notify};
+
+/// Something that happened during a run. Events arrive in order: every member is queued before
+/// any starts, a member's chunks arrive before its `MemberFinished`, and `RunFinished` is last.
+#[derive(Clone)]
+pub enum Event {
+    /// A member is waiting to run (members served from the cache are never queued)
+    MemberQueued(usize),
+    /// A member's process was spawned
+    MemberStarted(usize),
+    /// A line of a running member's output, with its totals so far
+    MemberChunk(usize, StreamProgress),
+    /// A member succeeded, failed, timed out or was cancelled
+    MemberFinished(MemberOutput),
+    /// A member changed these files in its sandbox; the changes were discarded
+    SandboxChanges(usize, Vec<String>),
+    /// Every member has settled; synthesis, if any, comes next
+    MembersDone { elapsed: Duration, budget_note: Option<String> },
+    SynthesisStarted,
+    SynthesisChunk(StreamProgress),
+    SynthesisFinished(SynthesisOutput),
+    /// The run is complete and saved; `execute` returns it next
+    RunFinished { elapsed: Duration },
+    /// A problem that does not stop the run, such as the history database failing to save
+    Warning(String),
+}
+
+impl Event {
+    /// The event's name in JSON output and server-sent events.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::MemberQueued(_) => "member_queued",
+            Event::MemberStarted(_) => "member_started",
+            Event::MemberChunk(..) => "member_chunk",
+            Event::MemberFinished(_) => "member_finished",
+            Event::SandboxChanges(..) => "sandbox_changes",
+            Event::MembersDone { .. } => "members_done",
+            Event::SynthesisStarted => "synthesis_started",
+            Event::SynthesisChunk(_) => "synthesis_chunk",
+            Event::SynthesisFinished(_) => "synthesis_finished",
+            Event::RunFinished { .. } => "run_finished",
+            Event::Warning(_) => "warning",
+        }
+    }
+}
+
+impl Run {
+    /// Bring a copy of a run up to date with an event from its council.
+    pub fn apply(&mut self, event: &Event) {
+        match event {
+            Event::MemberFinished(m) => {
+                if let Some(slot) = self.members.iter_mut().find(|s| s.id == m.id) {
+                    *slot = m.clone();
+                }
+            }
+            Event::MembersDone { elapsed, budget_note } => {
+                self.member_elapsed = *elapsed;
+                self.budget_note = budget_note.clone();
+            }
+            Event::SynthesisFinished(s) => self.synthesis = Some(s.clone()),
+            Event::RunFinished { elapsed } => self.total_elapsed = *elapsed,
+            _ => {}
+        }
+    }
+}
+
+/// Stops a running council: members still running are killed and synthesis is skipped.
+#[derive(Clone, Default)]
+pub struct CancelHandle(Arc<Notify>);
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        // A stored permit means a cancel before the runner starts waiting is not lost
+        self.0.notify_one();
+    }
+
+    async fn cancelled(&self) {
+        self.0.notified().await;
+    }
+}
+
+/// Runs a council's pending members, then its synthesis, saving to history as it goes.
+pub struct Council {
+    pub run: Run,
+    /// Database the run was inserted into; finished members and the result are saved to it
+    pub history: Option<History>,
+    /// Successful members are stored here
+    pub cache: Option<MemberCache>,
+    pub budget: Budget,
+    events: Option<mpsc::UnboundedSender<Event>>,
+    cancel: CancelHandle,
+    /// Problems found while setting up, reported once the run starts
+    warnings: Vec<String>,
+}
+
+impl Council {
+    /// A council for `run` with no history, cache or budget.
+    pub fn new(run: Run) -> Council {
+        Council {
+            run,
+            history: None,
+            cache: None,
+            budget: Budget::default(),
+            events: None,
+            cancel: CancelHandle::default(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// A council for a run built by `new_run`, with `config`'s budget, member cache and history:
+    /// cached members are filled in and the run is saved, so it has an id before it starts.
+    pub fn from_config(config: &Config, mut run: Run) -> Council {
+        let mut warnings = config.warnings.clone();
+
+        // The cache is opt-in and, like history, best-effort
+        let cache = if config.cache || config.refresh {
+            match MemberCache::open(&run.workdir, &run.settings, config.refresh) {
+                Ok(cache) => {
+                    for m in &mut run.members {
+                        cache.load(m);
+                    }
+                    Some(cache)
+                }
+                Err(e) => {
+                    warnings.push(format!("{} (cache disabled)", e));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // History is best-effort: a broken database should not stop the council
+        let history = if !config.history {
+            None
+        } else {
+            match History::open().and_then(|h| h.insert_run(&run).map(|id| (h, id))) {
+                Ok((h, id)) => {
+                    run.id = Some(id);
+                    Some(h)
+                }
+                Err(e) => {
+                    warnings.push(format!("{} (run will not be saved)", e));
+                    None
+                }
+            }
+        };
+
+        Council {
+            history,
+            cache,
+            budget: Budget::new(config.max_cost, config.max_tokens),
+            warnings,
+            ..Council::new(run)
+        }
+    }
+
+    /// Subscribe to the run's events. The stream ends once `execute` returns.
+    pub fn events(&mut self) -> mpsc::UnboundedReceiver<Event> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.events = Some(tx);
+        rx
+    }
+
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    fn emit(&self, event: Event) {
+        if let Some(tx) = &self.events {
+            let _ = tx.send(event);
+        }
+    }
+
+    /// Save and report a member that has settled.
+    fn record(&mut self, member: MemberOutput) {
+        self.emit(Event::MemberFinished(member.clone()));
+        if let (Some(c), MemberStatus::Success) = (&self.cache, member.status) {
+            if let Err(e) = c.store(&member) {
+                self.emit(Event::Warning(e));
+            }
+        }
+        if let (Some(h), Some(id)) = (&self.history, self.run.id) {
+            if let Err(e) = h.update_member(id, &member) {
+                self.emit(Event::Warning(e));
+            }
+        }
+        if let Some(slot) = self.run.members.iter_mut().find(|m| m.id == member.id) {
+            *slot = member;
+        }
+    }
+
+    /// Run every pending member, then synthesis. Fails only if a sandbox cannot be created.
+    pub async fn execute(mut self) -> Result<Run, String> {
+        for warning in std::mem::take(&mut self.warnings) {
+            self.emit(Event::Warning(warning));
+        }
+
+        let settings = self.run.settings.clone();
+        let pending: Vec<MemberOutput> = self
+            .run
+            .members
+            .iter()
+            .filter(|m| m.status == MemberStatus::Pending)
+            .cloned()
+            .collect();
+        for m in &pending {
+            self.emit(Event::MemberQueued(m.id));
+        }
+
+        // Sandboxes are created up front: concurrent `git worktree add` calls race on repo locks
+        let mut sandboxes: Vec<Option<Sandbox>> = Vec::with_capacity(pending.len());
+        if settings.isolation != Isolation::None {
+            for m in &pending {
+                let (isolation, workdir) = (settings.isolation, self.run.workdir.clone());
+                let label = format!("member-{}", m.id + 1);
+                let sandbox = tokio::task::spawn_blocking(move || Sandbox::create(isolation, &workdir, &label))
+                    .await
+                    .map_err(|e| format!("Cannot create sandbox: {}", e))??;
+                sandboxes.push(Some(sandbox));
+            }
+        } else {
+            sandboxes.resize_with(pending.len(), || None);
+        }
+
+        let (tx, mut rx) = mpsc::channel::<MemberOutput>(pending.len().max(1));
+        let mut handles = Vec::with_capacity(pending.len());
+        let start_time = std::time::Instant::now();
+
+        // Spawn all council members
+        for (mut member, sandbox) in pending.into_iter().zip(sandboxes) {
+            let tx = tx.clone();
+            let events = self.events.clone();
+            let timeout = settings.timeout;
+            let permission = settings.permissions;
+            let backend = settings.backend.clone();
+            let workdir = sandbox.as_ref().map(|s| s.workdir.clone()).unwrap_or_else(|| self.run.workdir.clone());
+
+            self.emit(Event::MemberStarted(member.id));
+
+            handles.push(tokio::spawn(async move {
+                let i = member.id;
+                let started = std::time::Instant::now();
+                let send = |event: Event| {
+                    if let Some(events) = &events {
+                        let _ = events.send(event);
+                    }
+                };
+                let report = |p: &StreamProgress| send(Event::MemberChunk(i, p.clone()));
+                let result = run_claude(
+                    &member.prompt,
+                    timeout,
+                    member.model.as_deref(),
+                    permission,
+                    Some(&workdir),
+                    &backend,
+                    Some(&report),
+                )
+                .await;
+                match result {
+                    Ok(reply) => {
+                        member.status = if reply.success { MemberStatus::Success } else { MemberStatus::Failed };
+                        member.text = reply.text;
+                        member.usage = reply.usage;
+                    }
+                    Err(e) => {
+                        member.status = match e {
+                            ClaudeError::TimedOut(_) => MemberStatus::TimedOut,
+                            ClaudeError::Failed(_) => MemberStatus::Failed,
+                        };
+                        member.text = format!("[Member {} error: {}]", i + 1, e);
+                    }
+                }
+
+                if let Some(sandbox) = sandbox {
+                    let changes = tokio::task::spawn_blocking(move || {
+                        let changes = sandbox.changed_files();
+                        drop(sandbox);
+                        changes
+                    })
+                    .await
+                    .unwrap_or_default();
+                    if !changes.is_empty() {
+                        send(Event::SandboxChanges(i, changes.clone()));
+                        member.text.push_str(&format!(
+                            "\n\n[Sandbox: member changed {} file(s), discarded]\n{}",
+                            changes.len(),
+                            changes.join("\n")
+                        ));
+                    }
+                }
+
+                member.elapsed = started.elapsed();
+                // The receiver only goes away if the runner itself was dropped
+                let _ = tx.send(member).await;
+            }));
+        }
+        drop(tx);
+
+        // Collect results
+        let cancel = self.cancel.clone();
+        let budget = self.budget.clone();
+        let mut cancelled = false;
+        loop {
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => {
+                    // Aborting drops each member's process (kill_on_drop) and sandbox
+                    handles.iter().for_each(|h| h.abort());
+                    cancelled = true;
+                    break;
+                }
+                reason = budget.exhausted() => {
+                    handles.iter().for_each(|h| h.abort());
+                    self.run.budget_note = Some(reason);
+                    break;
+                }
+                member = rx.recv() => {
+                    let Some(member) = member else { break };
+                    budget.spend(member.usage);
+                    self.record(member);
+                }
+            }
+        }
+
+        // Members that finished while the budget tripped still count, and were paid for
+        while let Ok(member) = rx.try_recv() {
+            budget.spend(member.usage);
+            self.record(member);
+        }
+        if self.run.budget_note.is_some() {
+            self.run.budget_note = budget.exceeded();
+        }
+
+        let unfinished: Vec<MemberOutput> = self
+            .run
+            .members
+            .iter()
+            .filter(|m| m.status == MemberStatus::Pending)
+            .map(|m| MemberOutput { status: MemberStatus::Cancelled, ..m.clone() })
+            .collect();
+        for member in unfinished {
+            self.record(member);
+        }
+
+        // Nothing to synthesize, or no budget left for it
+        let finished = !self.run.finished_members().is_empty();
+        let spent = if budget.covers_synthesis { budget.exceeded() } else { None };
+        if self.run.budget_note.is_none() {
+            self.run.budget_note = spent.clone();
+        }
+
+        self.run.member_elapsed = start_time.elapsed();
+        self.emit(Event::MembersDone { elapsed: self.run.member_elapsed, budget_note: self.run.budget_note.clone() });
+
+        if settings.synthesize && !cancelled && finished && spent.is_none() {
+            self.emit(Event::SynthesisStarted);
+            let events = self.events.clone();
+            let report = move |p: &StreamProgress| {
+                if let Some(events) = &events {
+                    let _ = events.send(Event::SynthesisChunk(p.clone()));
+                }
+            };
+            tokio::select! {
+                biased;
+                // Dropping the synthesis future kills its process
+                _ = cancel.cancelled() => {}
+                _ = synthesize(&mut self.run, Some(&report)) => {}
+            }
+            if let Some(s) = &self.run.synthesis {
+                budget.spend(s.usage);
+                self.emit(Event::SynthesisFinished(s.clone()));
+            }
+        }
+
+        self.run.total_elapsed = start_time.elapsed();
+        if let Some(h) = &self.history {
+            if let Err(e) = h.update_run(&self.run) {
+                self.emit(Event::Warning(e));
+            }
+        }
+        self.emit(Event::RunFinished { elapsed: self.run.total_elapsed });
+        Ok(self.run)
+    }
+}
+
+/// Synthesize the run's finished members into `run.synthesis`.
+pub async fn synthesize(run: &mut Run, progress: Option<&(dyn Fn(&StreamProgress) + Send + Sync)>) {
+    let settings = &run.settings;
+
+    let synthesis_start = std::time::Instant::now();
+    let synthesis_prompt = create_synthesis_prompt(
+        &run.finished_members(),
+        &run.task,
+        settings.synthesis_template.as_ref(),
+        settings.synthesis_instructions.as_deref(),
+    );
+    let synthesis_result = run_claude(
+        &synthesis_prompt,
+        settings.timeout,
+        settings.synthesis_model.as_deref().or(settings.model.as_deref()),
+        settings.synthesis_permissions,
+        Some(&run.workdir),
+        &settings.backend,
+        progress,
+    )
+    .await;
+
+    let synthesis = match synthesis_result {
+        Ok(reply) => SynthesisOutput { text: Ok(reply.text), usage: reply.usage, elapsed: synthesis_start.elapsed() },
+        Err(e) => SynthesisOutput { text: Err(e.to_string()), usage: Usage::default(), elapsed: synthesis_start.elapsed() },
+    };
+    run.synthesis = Some(synthesis);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Backend;
+
+    /// Sleeps for as many seconds as the prompt says, then answers with $0.01 and 150 tokens of usage.
+    const FAKE_BACKEND: &str = concat!(
+        r#"sleep "$2" 2>/dev/null; echo '{"type":"result","subtype":"success","is_error":false,"#,
+        r#""result":"- P1 done","total_cost_usd":0.01,"usage":{"input_tokens":100,"output_tokens":50}}'"#
+    );
+
+    /// A run of one member per entry of `delays`, against `FAKE_BACKEND`.
+    fn run_with_delays(delays: &[&str]) -> Run {
+        let mut config = Config::defaults();
+        config.num = delays.len();
+        config.backend = Backend {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), FAKE_BACKEND.to_string(), "fake-claude".to_string()],
+        };
+        let mut run = crate::run::new_run(&config, "Review".to_string()).unwrap();
+        // Mandatory lenses can make the roster larger than asked for
+        run.members.truncate(delays.len());
+        for (m, delay) in run.members.iter_mut().zip(delays) {
+            m.prompt = delay.to_string();
+        }
+        run
+    }
+
+    #[tokio::test]
+    async fn budget_cancels_the_rest_and_still_synthesizes() {
+        let mut council = Council::new(run_with_delays(&["0", "0.5", "30"]));
+        council.budget = Budget::new(Some(0.015), None);
+        let run = council.execute().await.unwrap();
+
+        let statuses: Vec<MemberStatus> = run.members.iter().map(|m| m.status).collect();
+        assert_eq!(statuses, [MemberStatus::Success, MemberStatus::Success, MemberStatus::Cancelled]);
+        let note = run.budget_note.unwrap_or_default();
+        assert!(note.starts_with("spent $0.0200 of"), "{}", note);
+        assert!(run.synthesis.as_ref().is_some_and(|s| s.text.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn members_finishing_together_are_all_charged() {
+        let mut council = Council::new(run_with_delays(&["0", "0", "0"]));
+        council.run.settings.synthesize = false;
+        council.budget = Budget::new(None, Some(100));
+        let run = council.execute().await.unwrap();
+
+        // However the results arrive, the note counts every member that finished
+        let finished = run.members.iter().filter(|m| m.status == MemberStatus::Success).count();
+        assert_eq!(run.budget_note, Some(format!("used {} of 100 tokens", finished * 150)));
+    }
+
+    #[tokio::test]
+    async fn spent_shared_budget_runs_nothing() {
+        let budget = Budget::new(Some(0.5), None);
+        budget.spend(Usage { cost_usd: 1.0, ..Usage::default() });
+        let mut council = Council::new(run_with_delays(&["0", "0"]));
+        council.budget = budget;
+        let run = council.execute().await.unwrap();
+
+        assert!(run.members.iter().all(|m| m.status == MemberStatus::Cancelled));
+        assert!(run.synthesis.is_none(), "no analyses to synthesize");
+        assert!(run.budget_note.is_some());
+    }
+
+    #[tokio::test]
+    async fn budget_covering_synthesis_skips_it_once_spent() {
+        let mut council = Council::new(run_with_delays(&["0"]));
+        council.budget = Budget::new(Some(0.005), None);
+        council.budget.covers_synthesis = true;
+        let run = council.execute().await.unwrap();
+
+        assert_eq!(run.members[0].status, MemberStatus::Success);
+        assert!(run.synthesis.is_none());
+    }
+
+    #[tokio::test]
+    async fn synthesis_is_charged_to_the_budget() {
+        let budget = Budget::new(None, Some(300));
+        let mut council = Council::new(run_with_delays(&["0"]));
+        council.budget = budget.clone();
+        let run = council.execute().await.unwrap();
+
+        assert!(run.synthesis.is_some());
+        assert_eq!(run.budget_note, None);
+        // 150 tokens for the member and 150 for the synthesis
+        assert_eq!(budget.exceeded().as_deref(), Some("used 300 of 300 tokens"));
+    }
+}
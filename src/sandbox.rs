@@ -0,0 +1,390 @@
+//! Throwaway worktrees and copies that keep members from touching the real checkout.
+
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// How each member's working directory is isolated from the real checkout.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Isolation {
+    /// Members run directly in the current directory
+    None,
+    /// Each member gets a detached git worktree of HEAD plus uncommitted changes
+    Worktree,
+    /// Each member gets a plain copy of the current directory
+    Copy,
+}
+
+impl Isolation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Isolation::None => "none",
+            Isolation::Worktree => "worktree",
+            Isolation::Copy => "copy",
+        }
+    }
+}
+
+/// A throwaway directory a single member runs in.
+pub struct Sandbox {
+    kind: Isolation,
+    /// Root of the throwaway tree
+    root: PathBuf,
+    /// Directory the member should run in (mirrors the caller's cwd inside `root`)
+    pub workdir: PathBuf,
+    /// Repository the worktree was added to, if any
+    repo: Option<PathBuf>,
+    /// Content fingerprint of every file before the member ran
+    baseline: HashMap<PathBuf, u64>,
+}
+
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+pub(crate) fn git_toplevel(dir: &Path) -> Option<PathBuf> {
+    let out = git(dir, &["rev-parse", "--show-toplevel"]).ok()?;
+    Some(PathBuf::from(String::from_utf8_lossy(&out).trim()))
+}
+
+/// Files git would show for `dir`: tracked plus untracked-but-not-ignored.
+pub(crate) fn git_visible_files(repo: &Path) -> Result<Vec<PathBuf>, String> {
+    let out = git(repo, &["ls-files", "-z", "--cached", "--others", "--exclude-standard"])?;
+    Ok(out
+        .split(|b| *b == 0)
+        .filter(|p| !p.is_empty())
+        .map(|p| PathBuf::from(String::from_utf8_lossy(p).into_owned()))
+        .collect())
+}
+
+fn copy_files(from: &Path, to: &Path, files: &[PathBuf]) -> Result<(), String> {
+    for rel in files {
+        let src = from.join(rel);
+        // Tracked files deleted in the working tree are listed but absent
+        if !src.is_file() {
+            continue;
+        }
+        let dst = to.join(rel);
+        if let Some(parent) = dst.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+        }
+        std::fs::copy(&src, &dst).map_err(|e| format!("Cannot copy {}: {}", src.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Directories `walk_files` leaves out: git's own, and dependency and build output that is
+/// large, generated and not worth copying or hashing.
+const SKIPPED_DIRS: [&str; 3] = [".git", "node_modules", "target"];
+
+/// Every file under `dir`, relative to `root`, for trees outside git.
+pub(crate) fn walk_files(root: &Path, dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.iter().any(|skipped| entry.file_name() == *skipped) {
+                walk_files(root, &path, out);
+            }
+        } else if file_type.is_file() {
+            if let Ok(rel) = path.strip_prefix(root) {
+                out.push(rel.to_path_buf());
+            }
+        }
+    }
+}
+
+/// A content hash of every file under `root`, leaving out what git ignores in a worktree and
+/// the directories `walk_files` skips elsewhere.
+pub(crate) fn fingerprint(root: &Path) -> HashMap<PathBuf, u64> {
+    let files = git_visible_files(root).unwrap_or_else(|_| {
+        let mut files = Vec::new();
+        walk_files(root, root, &mut files);
+        files
+    });
+    files
+        .into_iter()
+        .filter_map(|rel| {
+            let bytes = std::fs::read(root.join(&rel)).ok()?;
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            bytes.hash(&mut hasher);
+            Some((rel, hasher.finish()))
+        })
+        .collect()
+}
+
+/// A new, empty directory for a sandbox. The random suffix keeps councils running at once in
+/// one process (batches, per-directory reviews, the HTTP server) apart, and an existing path is
+/// never reused, so one council cannot clear another's tree.
+fn fresh_root(label: &str) -> Result<PathBuf, String> {
+    loop {
+        let name = format!("council-{}-{}-{:08x}", std::process::id(), label, rand::random::<u32>());
+        let root = std::env::temp_dir().join(name);
+        match std::fs::create_dir(&root) {
+            Ok(()) => return Ok(root),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Cannot create {}: {}", root.display(), e)),
+        }
+    }
+}
+
+impl Sandbox {
+    /// Build a throwaway tree mirroring `cwd` for member `label`.
+    pub fn create(kind: Isolation, cwd: &Path, label: &str) -> Result<Sandbox, String> {
+        let root = fresh_root(label)?;
+
+        // The guard exists before anything is created, so Drop cleans up after any error below
+        let mut sandbox = Sandbox {
+            kind,
+            root: root.clone(),
+            workdir: root.clone(),
+            repo: None,
+            baseline: HashMap::new(),
+        };
+
+        let toplevel = git_toplevel(cwd);
+        let source = match (kind, toplevel) {
+            (Isolation::Worktree, Some(top)) => {
+                let root_str = root.to_string_lossy().into_owned();
+                git(&top, &["worktree", "add", "--detach", "--quiet", &root_str, "HEAD"])?;
+                sandbox.repo = Some(top.clone());
+
+                // Bring over uncommitted tracked changes and untracked files
+                let diff = git(&top, &["diff", "HEAD", "--binary"])?;
+                if !diff.is_empty() {
+                    let mut child = std::process::Command::new("git")
+                        .arg("-C")
+                        .arg(&root)
+                        .args(["apply", "--whitespace=nowarn", "-"])
+                        .stdin(std::process::Stdio::piped())
+                        .spawn()
+                        .map_err(|e| format!("Cannot run git apply: {}", e))?;
+                    if let Some(mut stdin) = child.stdin.take() {
+                        stdin.write_all(&diff).map_err(|e| format!("git apply failed: {}", e))?;
+                    }
+                    let status = child.wait().map_err(|e| format!("git apply failed: {}", e))?;
+                    if !status.success() {
+                        return Err("Cannot apply uncommitted changes to worktree".to_string());
+                    }
+                }
+                let untracked = git(&top, &["ls-files", "-z", "--others", "--exclude-standard"])?;
+                let untracked: Vec<PathBuf> = untracked
+                    .split(|b| *b == 0)
+                    .filter(|p| !p.is_empty())
+                    .map(|p| PathBuf::from(String::from_utf8_lossy(p).into_owned()))
+                    .collect();
+                copy_files(&top, &root, &untracked)?;
+                top
+            }
+            (Isolation::Worktree, None) => {
+                return Err(format!("{} is not inside a git repository; use --isolate copy", cwd.display()));
+            }
+            (Isolation::Copy, Some(top)) => {
+                copy_files(&top, &root, &git_visible_files(&top)?)?;
+                top
+            }
+            (Isolation::Copy, None) => {
+                let mut files = Vec::new();
+                walk_files(cwd, cwd, &mut files);
+                copy_files(cwd, &root, &files)?;
+                cwd.to_path_buf()
+            }
+            (Isolation::None, _) => unreachable!("no sandbox without isolation"),
+        };
+
+        sandbox.workdir = match cwd.strip_prefix(&source) {
+            Ok(rel) => root.join(rel),
+            Err(_) => root.clone(),
+        };
+        std::fs::create_dir_all(&sandbox.workdir)
+            .map_err(|e| format!("Cannot create {}: {}", sandbox.workdir.display(), e))?;
+        sandbox.baseline = fingerprint(&root);
+        Ok(sandbox)
+    }
+
+    /// Files added (A), modified (M) or deleted (D) since the sandbox was created.
+    pub fn changed_files(&self) -> Vec<String> {
+        let now = fingerprint(&self.root);
+        let mut changes: Vec<String> = now
+            .iter()
+            .filter_map(|(path, hash)| match self.baseline.get(path) {
+                None => Some(format!("A {}", path.display())),
+                Some(old) if old != hash => Some(format!("M {}", path.display())),
+                _ => None,
+            })
+            .chain(
+                self.baseline
+                    .keys()
+                    .filter(|path| !now.contains_key(*path))
+                    .map(|path| format!("D {}", path.display())),
+            )
+            .collect();
+        changes.sort_by(|a, b| a[2..].cmp(&b[2..]));
+        changes
+    }
+}
+
+/// Delete a sandbox's tree, and unregister it from `repo` if it is a worktree.
+fn remove_tree(kind: Isolation, root: &Path, repo: Option<&Path>) {
+    if kind == Isolation::Worktree {
+        if let Some(repo) = repo {
+            let root_str = root.to_string_lossy().into_owned();
+            if git(repo, &["worktree", "remove", "--force", &root_str]).is_ok() {
+                return;
+            }
+        }
+    }
+    let _ = std::fs::remove_dir_all(root);
+    if let Some(repo) = repo {
+        let _ = git(repo, &["worktree", "prune"]);
+    }
+}
+
+// Cleanup lives in Drop so sandboxes of cancelled members are removed too
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let (kind, root, repo) = (self.kind, std::mem::take(&mut self.root), self.repo.take());
+        let cleanup = move || remove_tree(kind, &root, repo.as_deref());
+        // Removing a tree runs git and touches every file, so it stays off the runtime's threads
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => drop(handle.spawn_blocking(cleanup)),
+            Err(_) => cleanup(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory with `src/lib.rs` and build output in `target/`.
+    fn project(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("council-sandbox-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("target/debug")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(dir.join("target/debug/out"), "binary").unwrap();
+        dir
+    }
+
+    /// Turn `dir` into a repository with one commit that ignores `target/`.
+    fn commit(dir: &Path) {
+        std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .args(["-c", "user.name=council", "-c", "user.email=council@example.com"])
+                .args(args)
+                .stdout(std::process::Stdio::null())
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "init"]);
+    }
+
+    #[test]
+    fn copy_leaves_build_output_behind() {
+        let dir = project("copy");
+        let sandbox = Sandbox::create(Isolation::Copy, &dir, "copy").unwrap();
+        let root = sandbox.root.clone();
+
+        assert_eq!(std::fs::read_to_string(sandbox.workdir.join("src/lib.rs")).unwrap(), "fn a() {}\n");
+        assert!(!sandbox.workdir.join("target").exists());
+        assert!(sandbox.changed_files().is_empty());
+
+        drop(sandbox);
+        assert!(!root.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn changes_are_reported_as_added_modified_and_deleted() {
+        let dir = project("changes");
+        std::fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        let sandbox = Sandbox::create(Isolation::Copy, &dir, "changes").unwrap();
+
+        std::fs::write(sandbox.workdir.join("src/lib.rs"), "fn b() {}\n").unwrap();
+        std::fs::remove_file(sandbox.workdir.join("src/main.rs")).unwrap();
+        std::fs::write(sandbox.workdir.join("NOTES"), "todo\n").unwrap();
+        std::fs::create_dir_all(sandbox.workdir.join("node_modules/x")).unwrap();
+        std::fs::write(sandbox.workdir.join("node_modules/x/index.js"), "").unwrap();
+
+        assert_eq!(sandbox.changed_files(), vec!["A NOTES", "M src/lib.rs", "D src/main.rs"]);
+        // The real checkout is untouched
+        assert_eq!(std::fs::read_to_string(dir.join("src/lib.rs")).unwrap(), "fn a() {}\n");
+        drop(sandbox);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn worktree_carries_uncommitted_work_and_is_removed() {
+        let dir = project("worktree");
+        commit(&dir);
+        std::fs::write(dir.join("src/lib.rs"), "fn edited() {}\n").unwrap();
+        std::fs::write(dir.join("src/new.rs"), "fn new() {}\n").unwrap();
+
+        let sandbox = Sandbox::create(Isolation::Worktree, &dir.join("src"), "worktree").unwrap();
+        let root = sandbox.root.clone();
+        assert_eq!(sandbox.workdir, root.join("src"));
+        assert_eq!(std::fs::read_to_string(sandbox.workdir.join("lib.rs")).unwrap(), "fn edited() {}\n");
+        assert!(sandbox.workdir.join("new.rs").exists());
+        assert!(!root.join("target").exists());
+
+        // Ignored output a member produces is not a change
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("target/out"), "binary").unwrap();
+        std::fs::write(sandbox.workdir.join("new.rs"), "fn newer() {}\n").unwrap();
+        assert_eq!(sandbox.changed_files(), vec!["M src/new.rs"]);
+
+        drop(sandbox);
+        assert!(!root.exists());
+        let worktrees = git(&dir, &["worktree", "list", "--porcelain"]).unwrap();
+        assert_eq!(String::from_utf8_lossy(&worktrees).matches("worktree ").count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn worktree_needs_a_repository() {
+        let dir = project("no-repo");
+        let err = Sandbox::create(Isolation::Worktree, &dir, "no-repo").err().unwrap();
+        assert!(err.contains("not inside a git repository"), "{}", err);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dropping_inside_the_runtime_still_cleans_up() {
+        let dir = project("async");
+        let sandbox = Sandbox::create(Isolation::Copy, &dir, "async").unwrap();
+        let root = sandbox.root.clone();
+        drop(sandbox);
+        for _ in 0..100 {
+            if !root.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(!root.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,143 @@
+//! Watch mode: noticing when watched files change, and re-running the lenses a change concerns.
+
+use crate::config::Config;
+use crate::findings::extract_findings;
+use crate::run::{new_run, MemberOutput, Run};
+use crate::sandbox::{git_visible_files, walk_files};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Modification time and size of every watched file, by path.
+pub type Snapshot = HashMap<PathBuf, (Option<SystemTime>, u64)>;
+
+/// The state of every file under `paths`. Directories inside a git repository leave out what
+/// git ignores, so build output does not count as a change.
+pub fn snapshot(paths: &[PathBuf]) -> Snapshot {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let found = git_visible_files(path).unwrap_or_else(|_| {
+                let mut found = Vec::new();
+                walk_files(path, path, &mut found);
+                found
+            });
+            files.extend(found.into_iter().map(|rel| path.join(rel)));
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+        .into_iter()
+        .filter_map(|file| {
+            let meta = std::fs::metadata(&file).ok()?;
+            let file = file.strip_prefix(".").map(Path::to_path_buf).unwrap_or(file);
+            Some((file, (meta.modified().ok(), meta.len())))
+        })
+        .collect()
+}
+
+/// Files added, modified or removed between two snapshots, sorted.
+pub fn changed_files(old: &Snapshot, new: &Snapshot) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = new
+        .iter()
+        .filter(|(path, state)| old.get(*path) != Some(*state))
+        .map(|(path, _)| path.clone())
+        .chain(old.keys().filter(|path| !new.contains_key(*path)).cloned())
+        .collect();
+    changed.sort();
+    changed
+}
+
+/// Names of the members of `run` whose findings cite one of `changed`.
+pub fn relevant_lenses(run: &Run, changed: &[PathBuf]) -> Vec<String> {
+    run.members
+        .iter()
+        .filter(|m| m.status.has_output())
+        .filter(|m| {
+            extract_findings(&m.text).iter().filter_map(|f| f.location.as_ref()).any(|loc| {
+                let cited = Path::new(&loc.path);
+                changed.iter().any(|path| path.ends_with(cited) || cited.ends_with(path))
+            })
+        })
+        .map(|m| m.name.clone())
+        .collect()
+}
+
+/// The next run of a watch after `changed` files changed: `previous`'s roster, told what
+/// changed, narrowed to the members named in `lenses` if any. Members keep their ids from the
+/// full roster. A narrowed run skips synthesis, which `merge_rerun` would not keep.
+pub fn rerun(config: &Config, task: &str, previous: &Run, changed: &[PathBuf], lenses: &[String]) -> Result<Run, String> {
+    let mut config = config.clone();
+    config.seed = Some(previous.seed);
+    let listed: Vec<String> = changed.iter().map(|p| p.display().to_string()).collect();
+    let task = format!("{}\n\nChanged since the previous review: {}", task, listed.join(", "));
+    let mut run = new_run(&config, task)?;
+    if !lenses.is_empty() {
+        let roster = run.members.len();
+        run.members.retain(|m| lenses.contains(&m.name));
+        run.settings.synthesize &= run.members.len() == roster;
+    }
+    Ok(run)
+}
+
+/// Fold `latest` into `state`, the newest output of every lens so far. A run of only some
+/// lenses replaces theirs; the synthesis is only replaced by one over the whole roster.
+pub fn merge_rerun(state: &mut Run, latest: &Run) {
+    for m in latest.members.iter().filter(|m| m.status.has_output()) {
+        match state.members.iter_mut().find(|s| s.name == m.name) {
+            Some(s) => *s = MemberOutput { id: s.id, ..m.clone() },
+            None => state.members.push(MemberOutput { id: state.members.len(), ..m.clone() }),
+        }
+    }
+    if latest.members.len() == state.members.len() && latest.synthesis.is_some() {
+        state.synthesis = latest.synthesis.clone();
+    }
+    state.id = latest.id;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::MemberStatus;
+
+    fn config() -> Config {
+        let mut config = Config::defaults();
+        config.num = 5;
+        config.seed = Some(7);
+        config
+    }
+
+    #[test]
+    fn narrowed_rerun_keeps_member_ids() {
+        let config = config();
+        let previous = new_run(&config, "review".to_string()).unwrap();
+        let lenses = vec![previous.members[1].name.clone(), previous.members[3].name.clone()];
+
+        let run = rerun(&config, "review", &previous, &[PathBuf::from("src/a.rs")], &lenses).unwrap();
+        let ids: Vec<(usize, &str)> = run.members.iter().map(|m| (m.id, m.name.as_str())).collect();
+        assert_eq!(ids, vec![(1, lenses[0].as_str()), (3, lenses[1].as_str())]);
+        assert!(!run.settings.synthesize);
+        assert!(run.task.ends_with("Changed since the previous review: src/a.rs"));
+    }
+
+    #[test]
+    fn merged_rerun_replaces_only_the_lenses_that_ran() {
+        let config = config();
+        let mut state = new_run(&config, "review".to_string()).unwrap();
+        for m in &mut state.members {
+            m.status = MemberStatus::Success;
+            m.text = "- P2 old".to_string();
+        }
+        let lenses = vec![state.members[2].name.clone()];
+        let mut latest = rerun(&config, "review", &state, &[PathBuf::from("src/a.rs")], &lenses).unwrap();
+        latest.members[0].status = MemberStatus::Success;
+        latest.members[0].text = "- P1 new".to_string();
+
+        merge_rerun(&mut state, &latest);
+        assert_eq!(state.members.len(), 5);
+        let texts: Vec<&str> = state.members.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["- P2 old", "- P2 old", "- P1 new", "- P2 old", "- P2 old"]);
+        assert!(state.members.iter().enumerate().all(|(i, m)| m.id == i));
+    }
+}